@@ -1,30 +1,68 @@
 use yew::prelude::*;
-use crate::components::{DevPanel, GameView, State, UpgradeConfig, LogPanel};
-use crate::game::GameAction;
+use crate::components::{AchievementsPanel, AutobuyerPanel, ChallengesPanel, DevPanel, GameView, State, Statistics, UpgradeConfig, LogPanel, NewGame, OfflineSummary, PrestigePanel, SaveProblemsNotice, SlotPicker};
+use crate::game::{GameAction, GameState, Rng};
 use crate::hooks::{use_game_state, use_auto_save, use_auto_increment, GameStateHandle};
 use crate::predefined_states::load_predefined_states;
-use crate::upgrades::{load_upgrades_config, UpgradesConfig};
+use crate::storage::{LoadedSave, SaveError, SaveProblems};
+use crate::upgrades::UpgradesConfig;
 
 #[function_component(App)]
 pub fn app() -> Html {
-    let GameStateHandle { state, on_action } = use_game_state();
-    use_auto_save(state.clone());
-    use_auto_increment(state.clone());
-    let predefined_states = use_state(|| load_predefined_states());
-    let logs = use_state(|| vec![]);
+    let GameStateHandle { store, on_action, storage, loaded, offline_progress, save_problems } = use_game_state();
+    let state = store.state.clone();
+    use_auto_save(storage.clone(), state.clone(), loaded, on_action.clone());
+    use_auto_increment(on_action.clone());
+    let predefined_states = {
+        let upgrades_config = store.upgrades_config.clone();
+        use_state(move || load_predefined_states(&upgrades_config))
+    };
 
-    let upgrades_config = use_state(|| load_upgrades_config());
+    let upgrades_config = {
+        let store_config = store.upgrades_config.clone();
+        use_state(move || (*store_config).clone())
+    };
 
     let on_select_predefined_state = {
-        let state = state.clone();
+        let on_action = on_action.clone();
         let predefined_states = predefined_states.clone();
+        let save_problems = save_problems.clone();
         Callback::from(move |index: usize| {
-            if let Some(predefined_state) = predefined_states.get(index) {
-                state.set(predefined_state.clone());
+            if let Some(result) = predefined_states.get(index) {
+                let source = format!("State {}", index + 1);
+                save_problems.set(SaveProblems::from_result(&source, result));
+                if let Ok(loaded) = result {
+                    // Everyone picking this state would otherwise share its random events.
+                    let state = GameState {
+                        rng: Rng::new(js_sys::Date::now() as u64),
+                        ..loaded.state.clone()
+                    };
+                    on_action.emit(GameAction::Load(Box::new(state)));
+                }
+            }
+        })
+    };
+
+    let on_load_state = {
+        let on_action = on_action.clone();
+        let save_problems = save_problems.clone();
+        Callback::from(move |(source, result): (String, Result<LoadedSave, SaveError>)| {
+            save_problems.set(SaveProblems::from_result(&source, &result));
+            if let Ok(loaded) = result {
+                on_action.emit(GameAction::Load(Box::new(loaded.state)));
             }
         })
     };
 
+    let on_dismiss_save_problems = {
+        let save_problems = save_problems.clone();
+        Callback::from(move |_| save_problems.set(None))
+    };
+
+    let on_slot_save_problems = {
+        let save_problems = save_problems.clone();
+        Callback::from(move |problems: Option<SaveProblems>| save_problems.set(problems))
+    };
+
     let on_update_upgrades_config = {
         let upgrades_config = upgrades_config.clone();
         Callback::from(move |new_config: UpgradesConfig| {
@@ -32,37 +70,27 @@ pub fn app() -> Html {
         })
     };
 
-    let on_action_with_log = {
-        let on_action = on_action.clone();
-        let logs = logs.clone();
-        Callback::from(move |action: GameAction| {
-            let log_message = match &action {
-                GameAction::Click => "Clicked".to_string(),
-                GameAction::Reset => "Game reset".to_string(),
-                GameAction::UpdateGameParameter(param) => format!("Updated parameter: {:?}", param),
-                GameAction::BuyUpgrade(upgrade) => format!("Bought upgrade: {}", upgrade),
-            };
-            logs.set({
-                let mut new_logs = (*logs).clone();
-                new_logs.push(log_message);
-                new_logs
-            });
-            on_action.emit(action);
-        })
-    };
-
     html! {
         <div class="app-container">
             <div class="state-management-bar">
-                <State state={state.clone()} on_select_predefined_state={on_select_predefined_state.clone()} />
+                <State state={state.clone()} storage={storage.clone()} upgrades_config={store.upgrades_config.clone()} on_select_predefined_state={on_select_predefined_state.clone()} on_load_state={on_load_state.clone()} />
+                <SlotPicker state={state.clone()} storage={storage.clone()} upgrades_config={store.upgrades_config.clone()} on_action={on_action.clone()} on_save_problems={on_slot_save_problems} />
             </div>
             <div class="main-content">
                 <div class="game-view">
-                    <GameView state={state.clone()} on_action={on_action_with_log.clone()} />
-                    <LogPanel logs={logs.clone()} />
+                    <SaveProblemsNotice save_problems={(*save_problems).clone()} on_dismiss={on_dismiss_save_problems} />
+                    <OfflineSummary offline_progress={offline_progress} upgrades_config={store.upgrades_config.clone()} />
+                    <GameView state={state.clone()} upgrades_config={store.upgrades_config.clone()} on_action={on_action.clone()} />
+                    <AutobuyerPanel state={state.clone()} upgrades_config={store.upgrades_config.clone()} on_action={on_action.clone()} />
+                    <PrestigePanel state={state.clone()} upgrades_config={store.upgrades_config.clone()} on_action={on_action.clone()} />
+                    <NewGame state={state.clone()} upgrades_config={store.upgrades_config.clone()} on_action={on_action.clone()} />
+                    <ChallengesPanel state={state.clone()} upgrades_config={store.upgrades_config.clone()} on_action={on_action.clone()} />
+                    <AchievementsPanel state={state.clone()} upgrades_config={store.upgrades_config.clone()} />
+                    <Statistics state={state.clone()} upgrades_config={store.upgrades_config.clone()} />
+                    <LogPanel events={store.events.clone()} />
                 </div>
                 <div class="dev-panel">
-                    <DevPanel game_state={state.clone()} on_parameter_change={on_action_with_log.clone()} upgrades_config={upgrades_config.clone()} on_update_upgrades_config={on_update_upgrades_config.clone()} />
+                    <DevPanel game_state={state.clone()} on_parameter_change={on_action.clone()} on_action={on_action.clone()} upgrades_config={upgrades_config.clone()} on_update_upgrades_config={on_update_upgrades_config.clone()} />
                     <div class="sub-tab">
                         <UpgradeConfig game_state={state.clone()} upgrades_config={upgrades_config.clone()} />
                     </div>
@@ -70,4 +98,4 @@ pub fn app() -> Html {
             </div>
         </div>
     }
-}
\ No newline at end of file
+}
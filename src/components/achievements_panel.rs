@@ -0,0 +1,43 @@
+use crate::game::GameState;
+use crate::upgrades::UpgradesConfig;
+use std::rc::Rc;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct AchievementsPanelProps {
+    pub state: GameState,
+    pub upgrades_config: Rc<UpgradesConfig>,
+}
+
+#[function_component(AchievementsPanel)]
+pub fn achievements_panel(props: &AchievementsPanelProps) -> Html {
+    let state = &props.state;
+    let achievements = &props.upgrades_config.achievements;
+
+    html! {
+        <div class="achievements-panel">
+            <h2>{ "Achievements" }</h2>
+            <p>{ format!(
+                "Unlocked {} of {} (bonus: x{:.2})",
+                state.achievements.len(),
+                achievements.len(),
+                state.achievement_multiplier(&props.upgrades_config)
+            ) }</p>
+            <ul>
+                { for achievements.iter().map(|achievement| {
+                    let unlocked = state.achievements.contains(&achievement.id);
+                    html! {
+                        <li class={classes!("achievement", (!unlocked).then_some("locked"))}>
+                            <span class="achievement-icon">{ &achievement.icon }</span>
+                            <strong>{ &achievement.name }</strong>
+                            { format!(" — {}", achievement.description) }
+                            if let Some(bonus) = achievement.bonus {
+                                <span class="achievement-bonus">{ format!(" (x{})", bonus) }</span>
+                            }
+                        </li>
+                    }
+                }) }
+            </ul>
+        </div>
+    }
+}
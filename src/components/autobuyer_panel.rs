@@ -0,0 +1,121 @@
+use crate::game::{AutobuyRule, BigNum, GameAction, GameState};
+use crate::upgrades::UpgradesConfig;
+use std::rc::Rc;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct AutobuyerPanelProps {
+    pub state: GameState,
+    pub upgrades_config: Rc<UpgradesConfig>,
+    pub on_action: Callback<GameAction>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RuleKind {
+    Off,
+    WhenAffordable,
+    KeepReserve,
+    CheapestFirst,
+    BestPayback,
+}
+
+const RULE_KINDS: [(RuleKind, &str); 5] = [
+    (RuleKind::Off, "Off"),
+    (RuleKind::WhenAffordable, "Affordable"),
+    (RuleKind::KeepReserve, "Reserve"),
+    (RuleKind::CheapestFirst, "Cheapest"),
+    (RuleKind::BestPayback, "Payback"),
+];
+
+fn rule_kind(rule: Option<&AutobuyRule>) -> RuleKind {
+    match rule {
+        None => RuleKind::Off,
+        Some(AutobuyRule::WhenAffordable) => RuleKind::WhenAffordable,
+        Some(AutobuyRule::KeepReserve { .. }) => RuleKind::KeepReserve,
+        Some(AutobuyRule::CheapestFirst) => RuleKind::CheapestFirst,
+        Some(AutobuyRule::BestPayback) => RuleKind::BestPayback,
+    }
+}
+
+/// The rule for `kind`, keeping the reserve of the current rule if it has one.
+fn rule_for(kind: RuleKind, current: Option<&AutobuyRule>) -> Option<AutobuyRule> {
+    match kind {
+        RuleKind::Off => None,
+        RuleKind::WhenAffordable => Some(AutobuyRule::WhenAffordable),
+        RuleKind::KeepReserve => Some(match current {
+            Some(rule @ AutobuyRule::KeepReserve { .. }) => rule.clone(),
+            _ => AutobuyRule::KeepReserve {
+                reserve: BigNum::ZERO,
+            },
+        }),
+        RuleKind::CheapestFirst => Some(AutobuyRule::CheapestFirst),
+        RuleKind::BestPayback => Some(AutobuyRule::BestPayback),
+    }
+}
+
+#[function_component(AutobuyerPanel)]
+pub fn autobuyer_panel(props: &AutobuyerPanelProps) -> Html {
+    let state = &props.state;
+    let upgrades_config = &props.upgrades_config;
+    let rate = state.autobuy_rate(upgrades_config);
+    if rate <= 0.0 {
+        return html! {};
+    }
+
+    html! {
+        <div class="autobuyer-panel">
+            <h2>{ "Autobuyer" }</h2>
+            <p>{ format!("Purchases per second: {}", rate) }</p>
+            <ul>
+                { for upgrades_config.upgrades.iter().filter(|upgrade| state.is_unlocked(upgrade)).map(|upgrade| {
+                    let current = state.automation.rules.get(&upgrade.id);
+                    let selected = rule_kind(current);
+                    html! {
+                        <li class="autobuyer-rule">
+                            <span class="autobuyer-upgrade">{ format!("{} {}", upgrade.icon, upgrade.name) }</span>
+                            { for RULE_KINDS.iter().map(|(kind, label)| {
+                                let on_select = {
+                                    let on_action = props.on_action.clone();
+                                    let id = upgrade.id.clone();
+                                    let rule = rule_for(*kind, current);
+                                    Callback::from(move |_: MouseEvent| {
+                                        on_action.emit(GameAction::SetAutobuyRule(id.clone(), rule.clone()));
+                                    })
+                                };
+                                html! {
+                                    <button
+                                        class={classes!((selected == *kind).then_some("selected"))}
+                                        onclick={on_select}
+                                    >
+                                        { *label }
+                                    </button>
+                                }
+                            }) }
+                            if let Some(AutobuyRule::KeepReserve { reserve }) = current {
+                                <input
+                                    type="number"
+                                    min="0"
+                                    value={reserve.to_f64().to_string()}
+                                    onchange={{
+                                        let on_action = props.on_action.clone();
+                                        let id = upgrade.id.clone();
+                                        Callback::from(move |e: Event| {
+                                            if let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                                                if let Ok(reserve) = input.value().parse::<BigNum>() {
+                                                    on_action.emit(GameAction::SetAutobuyRule(
+                                                        id.clone(),
+                                                        Some(AutobuyRule::KeepReserve { reserve }),
+                                                    ));
+                                                }
+                                            }
+                                        })
+                                    }}
+                                />
+                            }
+                        </li>
+                    }
+                }) }
+            </ul>
+        </div>
+    }
+}
@@ -0,0 +1,80 @@
+use crate::game::{format_duration, GameAction, GameState};
+use crate::upgrades::UpgradesConfig;
+use std::rc::Rc;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct ChallengesPanelProps {
+    pub state: GameState,
+    pub upgrades_config: Rc<UpgradesConfig>,
+    pub on_action: Callback<GameAction>,
+}
+
+#[function_component(ChallengesPanel)]
+pub fn challenges_panel(props: &ChallengesPanelProps) -> Html {
+    let state = &props.state;
+    let upgrades_config = &props.upgrades_config;
+    let challenges = &upgrades_config.challenges;
+    if challenges.is_empty() {
+        return html! {};
+    }
+
+    let on_abandon = {
+        let on_action = props.on_action.clone();
+        Callback::from(move |_: MouseEvent| {
+            on_action.emit(GameAction::AbandonChallenge);
+        })
+    };
+
+    html! {
+        <div class="challenges-panel">
+            <h2>{ "Challenges" }</h2>
+            <p>{ format!(
+                "Completed {} of {} (bonus: x{:.2})",
+                state.challenges.completed.len(),
+                challenges.len(),
+                state.challenge_multiplier(upgrades_config)
+            ) }</p>
+            if let Some(active) = &state.challenges.active {
+                <div class="active-challenge">
+                    <strong>{ format!(
+                        "In challenge: {}",
+                        upgrades_config
+                            .challenge(&active.id)
+                            .map_or(active.id.as_str(), |definition| definition.name.as_str())
+                    ) }</strong>
+                    if let Some(time_left) = state.challenge_time_left() {
+                        <span>{ format!(" ({} left)", format_duration(time_left)) }</span>
+                    }
+                    <button onclick={on_abandon}>{ "Abandon" }</button>
+                </div>
+            }
+            <ul>
+                { for challenges.iter().map(|challenge| {
+                    let completed = state.challenges.completed.contains(&challenge.id);
+                    let on_start = {
+                        let on_action = props.on_action.clone();
+                        let id = challenge.id.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            on_action.emit(GameAction::StartChallenge(id.clone()));
+                        })
+                    };
+                    html! {
+                        <li class={classes!("challenge", completed.then_some("completed"))}>
+                            <span class="challenge-icon">{ &challenge.icon }</span>
+                            <strong>{ &challenge.name }</strong>
+                            { format!(" — {}", challenge.description) }
+                            <span class="challenge-bonus">{ format!(" (x{})", challenge.bonus) }</span>
+                            if completed {
+                                <span>{ " ✔" }</span>
+                            }
+                            <button onclick={on_start} disabled={state.challenges.active.is_some()}>
+                                { "Start" }
+                            </button>
+                        </li>
+                    }
+                }) }
+            </ul>
+        </div>
+    }
+}
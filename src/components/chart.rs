@@ -1,19 +1,22 @@
-use crate::game::GameState;
+use crate::game::{BigNum, GameState};
+use crate::upgrades::UpgradesConfig;
 use plotters::prelude::*;
 use plotters_canvas::CanvasBackend;
 use web_sys::HtmlCanvasElement;
 use yew::prelude::*;
 
-pub fn draw_chart(canvas_ref: NodeRef, state: GameState, x_range: f32, y_range: f32) {
+pub fn draw_chart(
+    canvas_ref: NodeRef,
+    state: GameState,
+    upgrades_config: &UpgradesConfig,
+    x_range: f32,
+    y_range: f32,
+) {
     if let Some(canvas) = canvas_ref.cast::<HtmlCanvasElement>() {
         let backend = CanvasBackend::with_canvas_object(canvas).unwrap();
         let root = backend.into_drawing_area();
         root.fill(&WHITE).unwrap();
 
-        // Pre-calculate some values
-        let cps = state.calculate_clicks_per_second();
-        let current_resources = state.counter as f64;
-
         // Reduce number of points (only plot 100 points)
         let step_size = (x_range as i32 / 100).max(1);
 
@@ -34,58 +37,71 @@ pub fn draw_chart(canvas_ref: NodeRef, state: GameState, x_range: f32, y_range:
             .draw()
             .unwrap();
 
-        // Draw resource progression with fewer points
-        chart
-            .draw_series(LineSeries::new(
-                (0..(x_range as i32)).step_by(step_size as usize).map(|x| {
-                    let time = x as f64;
-                    let resources = current_resources + (cps * time);
-                    (x as f32, resources as f32)
-                }),
-                &BLUE,
-            ))
-            .unwrap();
-        // Only draw upgrade lines if they're within the visible range
-        let upgrades = vec![
-            (
-                "Click Multiplier",
-                state.get_upgrade_cost("click_multiplier"),
-            ),
-            ("Auto Clicker", state.get_upgrade_cost("auto_clicker")),
-        ];
+        for (index, resource) in upgrades_config.resources.iter().enumerate() {
+            let color = Palette99::pick(index).to_rgba();
+            let current_resources = state.resources.get(&resource.id);
 
-        for (name, cost) in upgrades.iter() {
-            let time_to_upgrade = state.time_to_reach_resources(*cost as f64);
-
-            // Draw horizontal cost line
+            // Draw resource progression with fewer points
             chart
                 .draw_series(LineSeries::new(
-                    vec![(0f32, *cost as f32), (x_range, *cost as f32)],
-                    &RED.mix(0.5),
+                    (0..(x_range as i32)).step_by(step_size as usize).map(|x| {
+                        let resources =
+                            state.resources_at_time(&resource.id, x as f64, upgrades_config);
+                        (x as f32, resources.to_f64() as f32)
+                    }),
+                    &color,
                 ))
                 .unwrap()
-                .label(format!("{} ({})", name, cost))
-                .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED.mix(0.5)));
+                .label(format!("{} {}", resource.icon, resource.name))
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &color));
 
             // Add current resources line
+            let current_y = current_resources.to_f64() as f32;
             chart
                 .draw_series(LineSeries::new(
-                    vec![
-                        (0f32, state.counter as f32),
-                        (x_range, state.counter as f32),
-                    ],
-                    &GREEN.mix(0.3),
+                    vec![(0f32, current_y), (x_range, current_y)],
+                    &color.mix(0.3),
+                ))
+                .unwrap();
+        }
+
+        // Only draw upgrade lines if they're within the visible range
+        let upgrades: Vec<(String, &str, BigNum)> = upgrades_config
+            .upgrades
+            .iter()
+            .map(|upgrade| {
+                (
+                    upgrade.name.clone(),
+                    upgrades_config.cost_resource(upgrade),
+                    state.upgrade_cost(upgrade),
+                )
+            })
+            .collect();
+
+        for (name, resource, cost) in upgrades.iter() {
+            let time_to_upgrade = state.time_to_reach_resources(resource, *cost, upgrades_config);
+            let cost_y = cost.to_f64() as f32;
+
+            // Draw horizontal cost line
+            chart
+                .draw_series(LineSeries::new(
+                    vec![(0f32, cost_y), (x_range, cost_y)],
+                    &RED.mix(0.5),
                 ))
                 .unwrap()
-                .label("Current Resources")
-                .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &GREEN.mix(0.3)));
+                .label(format!(
+                    "{} ({})",
+                    name,
+                    upgrades_config.format_amount(resource, *cost)
+                ))
+                .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED.mix(0.5)));
 
             // Only draw time marker if we need time to reach the upgrade
             if time_to_upgrade > 0.0 && time_to_upgrade <= x_range as f64 {
                 // Draw time marker for future upgrades
                 chart
                     .draw_series(PointSeries::of_element(
-                        vec![(time_to_upgrade as f32, *cost as f32)],
+                        vec![(time_to_upgrade as f32, cost_y)],
                         4,
                         &GREEN,
                         &|coord, size, style| {
@@ -103,7 +119,7 @@ pub fn draw_chart(canvas_ref: NodeRef, state: GameState, x_range: f32, y_range:
                 // Draw "Available Now" marker
                 chart
                     .draw_series(PointSeries::of_element(
-                        vec![(0f32, *cost as f32)],
+                        vec![(0f32, cost_y)],
                         4,
                         &GREEN,
                         &|coord, size, style| {
@@ -120,6 +136,38 @@ pub fn draw_chart(canvas_ref: NodeRef, state: GameState, x_range: f32, y_range:
             }
         }
 
+        // Mark what reaching each upgrade's next milestone costs and when
+        for upgrade in &upgrades_config.upgrades {
+            let Some(milestone) = state.next_milestone(upgrade) else {
+                continue;
+            };
+            let levels = milestone.level - state.upgrades.level(&upgrade.id);
+            let cost = state.bulk_upgrade_cost(upgrade, levels);
+            let resource = upgrades_config.cost_resource(upgrade);
+            let time_to_milestone = state
+                .time_to_reach_resources(resource, cost, upgrades_config)
+                .max(1.0);
+            if time_to_milestone > x_range as f64 {
+                continue;
+            }
+            let label = format!(
+                "{} ★{} (x{})",
+                upgrade.name, milestone.level, milestone.multiplier
+            );
+            chart
+                .draw_series(PointSeries::of_element(
+                    vec![(time_to_milestone as f32, cost.to_f64() as f32)],
+                    6,
+                    &BLUE,
+                    &|coord, size, style| {
+                        EmptyElement::at(coord)
+                            + TriangleMarker::new((0, 0), size, style.filled())
+                            + Text::new(label.clone(), (10, 5), ("sans-serif", 14))
+                    },
+                ))
+                .unwrap();
+        }
+
         chart
             .configure_series_labels()
             .background_style(&WHITE.mix(0.8))
@@ -1,6 +1,6 @@
 use crate::components::chart::draw_chart;
 use crate::game::{GameAction, GameParameter, GameState};
-use crate::upgrades::{load_upgrades_config, UpgradeParameters, UpgradesConfig};
+use crate::upgrades::{UpgradeDefinition, UpgradeEffect, UpgradesConfig, DEFAULT_COST_FORMULA};
 use crate::utils::file::save_to_file;
 use gloo_timers::callback::Interval;
 use yew::prelude::*;
@@ -11,14 +11,79 @@ pub enum DevPanelAction {
     UpdateAutoClickerEfficiency(f64),
 }
 
+impl From<DevPanelAction> for GameAction {
+    fn from(action: DevPanelAction) -> Self {
+        match action {
+            DevPanelAction::UpdateBaseMultiplier(value) => {
+                GameAction::UpdateGameParameter(GameParameter::BaseMultiplier(value))
+            }
+            DevPanelAction::UpdateCostScaling(value) => {
+                GameAction::UpdateGameParameter(GameParameter::CostScaling(value))
+            }
+            DevPanelAction::UpdateAutoClickerEfficiency(value) => {
+                GameAction::UpdateGameParameter(GameParameter::AutoClickerEfficiency(value))
+            }
+        }
+    }
+}
+
 #[derive(Properties, PartialEq)]
 pub struct DevPanelProps {
-    pub game_state: UseStateHandle<GameState>,
+    pub game_state: GameState,
     pub on_parameter_change: Callback<GameAction>,
+    pub on_action: Callback<GameAction>,
     pub upgrades_config: UseStateHandle<UpgradesConfig>,
     pub on_update_upgrades_config: Callback<UpgradesConfig>,
 }
 
+/// The cost and effect formulas of `upgrade` as the game evaluates them.
+fn upgrade_formulas(
+    upgrade: &UpgradeDefinition,
+    upgrades_config: &UpgradesConfig,
+    game_state: &GameState,
+) -> String {
+    let cost = match &upgrade.cost {
+        Some(cost) => cost.to_string(),
+        None => DEFAULT_COST_FORMULA.to_string(),
+    };
+    let amount = upgrade.effect.amount();
+    let effect = match &upgrade.effect {
+        UpgradeEffect::ClickMultiplier { .. } => {
+            format!("click multiplier += level * ({})", amount)
+        }
+        UpgradeEffect::AutoClicker { .. } => format!(
+            "{}/s += owned * ({}) * click multiplier * base_multiplier * efficiency",
+            upgrades_config
+                .produced_resource(&upgrade.effect)
+                .unwrap_or_default(),
+            amount
+        ),
+        UpgradeEffect::Generator { produces, .. } => {
+            format!("{}/s += owned * ({}) * efficiency", produces, amount)
+        }
+        UpgradeEffect::Autobuyer { .. } => {
+            format!("autobuyer purchases/s += level * ({})", amount)
+        }
+    };
+    let milestones = if upgrade.milestones.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " * milestones [x{}]",
+            game_state.milestone_multiplier(upgrade)
+        )
+    };
+    format!(
+        "{}: cost = {} [base_cost = {}, cost_scaling = {}]; {}{}",
+        upgrade.id,
+        cost,
+        upgrade.base_cost,
+        game_state.cost_scaling_for(upgrade),
+        effect,
+        milestones
+    )
+}
+
 #[derive(PartialEq, Clone)]
 pub enum ScaleType {
     Linear,
@@ -36,16 +101,29 @@ pub fn dev_panel(props: &DevPanelProps) -> Html {
     {
         let canvas_ref = canvas_ref.clone();
         let state = props.game_state.clone();
+        let upgrades_config = props.upgrades_config.clone();
         let x_range = *x_range;
         let y_range = *y_range;
 
         use_effect(move || {
             // Initial draw
-            draw_chart(canvas_ref.clone(), (*state).clone(), x_range, y_range);
+            draw_chart(
+                canvas_ref.clone(),
+                state.clone(),
+                &upgrades_config,
+                x_range,
+                y_range,
+            );
 
             // Set up interval for subsequent draws
             let interval = Interval::new(200, move || {
-                draw_chart(canvas_ref.clone(), (*state).clone(), x_range, y_range);
+                draw_chart(
+                    canvas_ref.clone(),
+                    state.clone(),
+                    &upgrades_config,
+                    x_range,
+                    y_range,
+                );
             });
 
             // Cleanup function
@@ -86,7 +164,7 @@ pub fn dev_panel(props: &DevPanelProps) -> Html {
                         GameParameter::BaseMultiplier(value),
                     ));
                     let mut new_config = (*upgrades_config).clone();
-                    new_config.click_multiplier.multiplier = Some(value);
+                    new_config.defaults.base_multiplier = value;
                     on_update_upgrades_config.emit(new_config);
                 }
             }
@@ -104,7 +182,7 @@ pub fn dev_panel(props: &DevPanelProps) -> Html {
                         GameParameter::CostScaling(value),
                     ));
                     let mut new_config = (*upgrades_config).clone();
-                    new_config.click_multiplier.cost_scaling = value;
+                    new_config.defaults.cost_scaling = value;
                     on_update_upgrades_config.emit(new_config);
                 }
             }
@@ -122,7 +200,7 @@ pub fn dev_panel(props: &DevPanelProps) -> Html {
                         GameParameter::AutoClickerEfficiency(value),
                     ));
                     let mut new_config = (*upgrades_config).clone();
-                    new_config.auto_clicker.efficiency = Some(value);
+                    new_config.defaults.auto_clicker_efficiency = value;
                     on_update_upgrades_config.emit(new_config);
                 }
             }
@@ -220,32 +298,49 @@ pub fn dev_panel(props: &DevPanelProps) -> Html {
                 </div>
             </div>
             <button onclick={on_save_upgrades}>{ "Save Upgrades" }</button>
+            <div class="buffs">
+                <h3>{"Buffs"}</h3>
+                { for props.upgrades_config.buffs.iter().map(|buff| {
+                    let on_action = props.on_action.clone();
+                    let id = buff.id.clone();
+                    html! {
+                        <button onclick={Callback::from(move |_| on_action.emit(GameAction::ActivateBuff(id.clone())))}>
+                            { format!("{} {}", buff.icon, buff.name) }
+                        </button>
+                    }
+                }) }
+            </div>
             <div class="formulas">
                 <h3>{"Current Formulas"}</h3>
                 <pre>
-                    {"Cost = base_cost * (1.15 ^ level)\n"}
-                    {"Production = base_prod * (multiplier ^ level)"}
+                    { for props.upgrades_config.upgrades.iter().map(|upgrade| {
+                        format!("{}\n", upgrade_formulas(upgrade, &props.upgrades_config, &props.game_state))
+                    }) }
                 </pre>
             </div>
             <div class="statistics">
                 <h3>{"Real-time Statistics"}</h3>
-                <p>{format!("Current CPS: {:.2}", props.game_state.calculate_clicks_per_second())}</p>
-                <p>{format!("Time to next upgrade: {:.2}s", props.game_state.time_to_next_upgrade())}</p>
+                <p>{format!("Current CPS: {:.2}", props.game_state.calculate_clicks_per_second(&props.upgrades_config))}</p>
+                <p>{format!("Time to next upgrade: {:.2}s", props.game_state.time_to_next_upgrade(&props.upgrades_config))}</p>
             </div>
             <div class="projections">
                 <h3>{"Resource Projections"}</h3>
                 <table>
                     <tr>
                         <th>{"Time"}</th>
-                        <th>{"Resources"}</th>
+                        { for props.upgrades_config.resources.iter().map(|resource| html! {
+                            <th>{format!("{} {}", resource.icon, resource.name)}</th>
+                        }) }
                     </tr>
                     {
                         [30.0, 60.0, 300.0, 600.0].iter().map(|&seconds| {
-                            let resources = props.game_state.resources_at_time(seconds);
                             html! {
                                 <tr>
                                     <td>{format!("{:.0} seconds", seconds)}</td>
-                                    <td>{format!("{:.0} resources", resources)}</td>
+                                    { for props.upgrades_config.resources.iter().map(|resource| {
+                                        let amount = props.game_state.resources_at_time(&resource.id, seconds, &props.upgrades_config);
+                                        html! { <td>{format!("{:.0}", amount)}</td> }
+                                    }) }
                                 </tr>
                             }
                         }).collect::<Html>()
@@ -1,15 +1,27 @@
-use crate::game::{GameAction, GameState};
+use crate::game::{BuyAmount, GameAction, GameState};
+use crate::upgrades::UpgradesConfig;
+use std::rc::Rc;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
 pub struct GameViewProps {
-    pub state: UseStateHandle<GameState>,
+    pub state: GameState,
+    pub upgrades_config: Rc<UpgradesConfig>,
     pub on_action: Callback<GameAction>,
 }
 
+const BUY_AMOUNTS: [(BuyAmount, &str); 4] = [
+    (BuyAmount::Levels(1), "x1"),
+    (BuyAmount::Levels(10), "x10"),
+    (BuyAmount::Levels(100), "x100"),
+    (BuyAmount::Max, "Max"),
+];
+
 #[function_component(GameView)]
 pub fn game_view(props: &GameViewProps) -> Html {
-    let state = props.state.clone();
+    let state = &props.state;
+    let upgrades_config = &props.upgrades_config;
+    let buy_amount = use_state(|| BuyAmount::Levels(1));
     let on_click = {
         let on_action = props.on_action.clone();
         Callback::from(move |_: MouseEvent| {
@@ -17,61 +29,132 @@ pub fn game_view(props: &GameViewProps) -> Html {
         })
     };
     let on_reset = {
+        let on_action = props.on_action.clone();
+        let difficulty = state.difficulty.clone();
+        Callback::from(move |_: MouseEvent| {
+            on_action.emit(GameAction::Reset(difficulty.clone()));
+        })
+    };
+    let on_collect_golden_click = {
         let on_action = props.on_action.clone();
         Callback::from(move |_: MouseEvent| {
-            on_action.emit(GameAction::Reset);
+            on_action.emit(GameAction::CollectGoldenClick);
         })
     };
     let on_buy_upgrade = {
         let on_action = props.on_action.clone();
+        let buy_amount = *buy_amount;
         Callback::from(move |upgrade_name: String| {
-            on_action.emit(GameAction::BuyUpgrade(upgrade_name));
+            on_action.emit(GameAction::BuyUpgrade(upgrade_name, buy_amount));
         })
     };
-    let x2_upgrade_cost = state.get_upgrade_cost("click_multiplier");
-    let auto_click_cost = state.get_upgrade_cost("auto_clicker");
 
     html! {
         <div>
             <div class="upgrades">
                 <h2>{ "Upgrades" }</h2>
+                <div class="buy-amount-selector">
+                    { for BUY_AMOUNTS.iter().map(|(amount, label)| {
+                        let on_select = {
+                            let buy_amount = buy_amount.clone();
+                            let amount = *amount;
+                            Callback::from(move |_: MouseEvent| buy_amount.set(amount))
+                        };
+                        html! {
+                            <button
+                                class={classes!((*buy_amount == *amount).then_some("selected"))}
+                                onclick={on_select}
+                            >
+                                { *label }
+                            </button>
+                        }
+                    }) }
+                </div>
                 <div class="upgrade-list">
-                    <div
-                        class={classes!("upgrade-square", (state.upgrades.click_multiplier > 0).then_some("active"))}
-                        onclick={Callback::from({
-                            let on_buy_upgrade = on_buy_upgrade.clone();
-                            move |_| on_buy_upgrade.emit("click_multiplier".to_string())
-                        })}
-                        title={format!(
-                            "Available in: {:.1}s",
-                            state.time_to_reach_resources(state.get_upgrade_cost("click_multiplier") as f64)
-                        )}
-                    >
-                        <div class="upgrade-icon">{ "⚡" }</div>
-                        <span class="upgrade-text">{ format!("Upgrade x2 (Cost: {})", x2_upgrade_cost) }</span>
-                    </div>
-                    <div
-                        class={classes!("upgrade-square", (state.upgrades.auto_clicker > 0).then_some("active"))}
-                        onclick={Callback::from({
-                            let on_buy_upgrade = on_buy_upgrade.clone();
-                            move |_| on_buy_upgrade.emit("auto_clicker".to_string())
-                        })}
-                        title={format!(
-                            "Available in: {:.1}s",
-                            state.time_to_reach_resources(state.get_upgrade_cost("auto_clicker") as f64)
-                        )}
-                    >
-                        <div class="upgrade-icon">{ "⏳" }</div>
-                        <span class="upgrade-text">{ format!("Auto-Click (Cost: {})", auto_click_cost) }</span>
-                    </div>
+                    { for upgrades_config.upgrades.iter().map(|upgrade| {
+                        let locked_reason = state.locked_reason(upgrade, upgrades_config);
+                        if let Some(reason) = locked_reason {
+                            if upgrade.hidden_while_locked {
+                                return html! {};
+                            }
+                            return html! {
+                                <div class="upgrade-square locked" title={reason.clone()}>
+                                    <div class="upgrade-icon">{ "🔒" }</div>
+                                    <span class="upgrade-text">{ format!("{}: {}", upgrade.name, reason) }</span>
+                                </div>
+                            };
+                        }
+                        let levels = match *buy_amount {
+                            BuyAmount::Levels(levels) => levels,
+                            BuyAmount::Max => state.max_affordable_levels(upgrade, upgrades_config).max(1),
+                        };
+                        let cost = state.bulk_upgrade_cost(upgrade, levels);
+                        let cost_resource = upgrades_config.cost_resource(upgrade);
+                        html! {
+                            <div
+                                class={classes!("upgrade-square", (state.upgrades.level(&upgrade.id) > 0).then_some("active"))}
+                                onclick={Callback::from({
+                                    let on_buy_upgrade = on_buy_upgrade.clone();
+                                    let id = upgrade.id.clone();
+                                    move |_| on_buy_upgrade.emit(id.clone())
+                                })}
+                                title={format!(
+                                    "Available in: {:.1}s",
+                                    state.time_to_reach_resources(cost_resource, cost, upgrades_config)
+                                )}
+                            >
+                                <div class="upgrade-icon">{ &upgrade.icon }</div>
+                                if !state.generated.get(&upgrade.id).is_zero() {
+                                    <span class="upgrade-owned">{ format!("Owned: {}", state.owned(&upgrade.id).floor()) }</span>
+                                }
+                                <span class="upgrade-text">{ format!(
+                                    "{} x{} (Cost: {})",
+                                    upgrade.name,
+                                    levels,
+                                    upgrades_config.format_amount(cost_resource, cost)
+                                ) }</span>
+                            </div>
+                        }
+                    }) }
                 </div>
             </div>
             <div class="game-panel">
                 <h1>{ "Idle Clicker Game" }</h1>
-                <p>{ "Current count: " }{ state.counter }</p>
-                <p>{ "Clicks per second: " }{ state.calculate_clicks_per_second() }</p>
-                <p>{ "Click value: " }{ state.calculate_click_value() }</p>
-                <p>{ "Multiplier: " }{ (1 + state.upgrades.click_multiplier) }</p>
+                <div class="buff-badges">
+                    { for state.buffs.iter().map(|buff| {
+                        let (icon, name) = upgrades_config
+                            .buff(&buff.id)
+                            .map_or(("", buff.id.as_str()), |definition| {
+                                (definition.icon.as_str(), definition.name.as_str())
+                            });
+                        html! {
+                            <span class="buff-badge" title={format!("{} (x{})", name, buff.multiplier)}>
+                                { format!("{} {:.0}s", icon, buff.remaining(state.statistics.time_played).ceil()) }
+                            </span>
+                        }
+                    }) }
+                </div>
+                { for upgrades_config.resources.iter().map(|resource| html! {
+                    <p>{ format!(
+                        "{} {}: {} ({}/s)",
+                        resource.icon,
+                        resource.name,
+                        state.resources.get(&resource.id).floor(),
+                        state.production_per_second(&resource.id, upgrades_config)
+                    ) }</p>
+                }) }
+                <p>{ "Clicks per second: " }{ state.calculate_clicks_per_second(upgrades_config).to_string() }</p>
+                <p>{ "Click value: " }{ state.calculate_click_value(upgrades_config).to_string() }</p>
+                <p>{ "Multiplier: " }{ state.click_multiplier(upgrades_config) }</p>
+                if let Some(golden_click) = &state.golden_click {
+                    <button
+                        class="golden-click"
+                        style={format!("left: {:.0}%; top: {:.0}%;", golden_click.x * 90.0, golden_click.y * 90.0)}
+                        onclick={on_collect_golden_click}
+                    >
+                        { "🌟" }
+                    </button>
+                }
                 <button onclick={on_click}>{ "Click me!" }</button>
                 <button onclick={on_reset}>{ "Reset" }</button>
             </div>
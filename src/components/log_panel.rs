@@ -1,8 +1,9 @@
+use crate::game::GameEvent;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
 pub struct LogPanelProps {
-    pub logs: UseStateHandle<Vec<String>>,
+    pub events: Vec<GameEvent>,
 }
 
 #[function_component(LogPanel)]
@@ -11,8 +12,8 @@ pub fn log_panel(props: &LogPanelProps) -> Html {
         <div class="log-panel">
             <h2>{ "Game Logs" }</h2>
             <ul>
-                { for props.logs.iter().map(|log| html! { <li>{ log }</li> }) }
+                { for props.events.iter().map(|event| html! { <li>{ event.to_string() }</li> }) }
             </ul>
         </div>
     }
-}
\ No newline at end of file
+}
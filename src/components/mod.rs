@@ -1,13 +1,31 @@
 
+mod achievements_panel;
+mod autobuyer_panel;
+mod challenges_panel;
 mod chart;
 mod dev_panel;
 mod game_view;
 mod state;
+mod statistics;
 mod upgrade_config;
 mod log_panel;
+mod new_game;
+mod offline_summary;
+mod prestige_panel;
+mod save_problems_notice;
+mod slot_picker;
 
-pub use dev_panel::{DevPanel, DevPanelAction};
+pub use achievements_panel::AchievementsPanel;
+pub use autobuyer_panel::AutobuyerPanel;
+pub use challenges_panel::ChallengesPanel;
+pub use dev_panel::DevPanel;
 pub use game_view::GameView;
 pub use state::State;
+pub use statistics::Statistics;
 pub use upgrade_config::UpgradeConfig;
 pub use log_panel::LogPanel;
+pub use new_game::NewGame;
+pub use offline_summary::OfflineSummary;
+pub use prestige_panel::PrestigePanel;
+pub use save_problems_notice::SaveProblemsNotice;
+pub use slot_picker::SlotPicker;
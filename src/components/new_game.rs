@@ -0,0 +1,128 @@
+use crate::game::{is_valid_parameter, Difficulty, GameAction, GameState, CUSTOM_DIFFICULTY};
+use crate::upgrades::{DifficultyMultipliers, UpgradesConfig};
+use std::rc::Rc;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct NewGameProps {
+    pub state: GameState,
+    pub upgrades_config: Rc<UpgradesConfig>,
+    pub on_action: Callback<GameAction>,
+}
+
+type MultiplierField = fn(&mut DifficultyMultipliers) -> &mut f64;
+
+const CUSTOM_FIELDS: [(&str, MultiplierField); 5] = [
+    ("Base multiplier", |multipliers| &mut multipliers.base_multiplier),
+    ("Cost scaling", |multipliers| &mut multipliers.cost_scaling),
+    ("Auto clicker efficiency", |multipliers| {
+        &mut multipliers.auto_clicker_efficiency
+    }),
+    ("Base cost", |multipliers| &mut multipliers.base_cost),
+    ("Offline efficiency", |multipliers| {
+        &mut multipliers.offline_efficiency
+    }),
+];
+
+#[function_component(NewGame)]
+pub fn new_game(props: &NewGameProps) -> Html {
+    let upgrades_config = &props.upgrades_config;
+    let selected = {
+        let preset = props.state.difficulty.preset.clone();
+        use_state(move || preset)
+    };
+    let custom = {
+        let multipliers = props.state.difficulty.multipliers.clone();
+        use_state(move || multipliers)
+    };
+    let invalid_field = use_state(|| None::<&'static str>);
+
+    let on_start = {
+        let on_action = props.on_action.clone();
+        let upgrades_config = upgrades_config.clone();
+        let selected = selected.clone();
+        let custom = custom.clone();
+        Callback::from(move |_: MouseEvent| {
+            let difficulty = match upgrades_config.difficulty(&selected) {
+                Some(preset) => Difficulty::from_preset(preset),
+                None => Difficulty::custom((*custom).clone()),
+            };
+            on_action.emit(GameAction::Reset(difficulty));
+        })
+    };
+
+    let presets = upgrades_config
+        .difficulties
+        .iter()
+        .map(|preset| (preset.id.as_str(), preset.name.as_str(), preset.description.as_str()))
+        .chain(std::iter::once((
+            CUSTOM_DIFFICULTY,
+            "Custom",
+            "Pick your own multipliers.",
+        )));
+
+    html! {
+        <div class="new-game">
+            <h2>{ "New Game" }</h2>
+            <p>{ format!("Current difficulty: {}", props.state.difficulty.name(upgrades_config)) }</p>
+            <div class="difficulty-selector">
+                { for presets.map(|(id, name, description)| {
+                    let on_select = {
+                        let selected = selected.clone();
+                        let id = id.to_string();
+                        Callback::from(move |_: MouseEvent| selected.set(id.clone()))
+                    };
+                    html! {
+                        <button
+                            class={classes!((*selected == id).then_some("selected"))}
+                            title={description.to_string()}
+                            onclick={on_select}
+                        >
+                            { name }
+                        </button>
+                    }
+                }) }
+            </div>
+            if *selected == CUSTOM_DIFFICULTY {
+                <div class="custom-difficulty">
+                    { for CUSTOM_FIELDS.iter().map(|(label, field)| {
+                        let (label, field) = (*label, *field);
+                        let mut multipliers = (*custom).clone();
+                        let value = *field(&mut multipliers);
+                        let on_change = {
+                            let custom = custom.clone();
+                            let invalid_field = invalid_field.clone();
+                            Callback::from(move |e: Event| {
+                                if let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                                    match input.value().parse::<f64>() {
+                                        // The same rule loading a save applies.
+                                        Ok(new_value) if is_valid_parameter(new_value) => {
+                                            let mut multipliers = (*custom).clone();
+                                            *field(&mut multipliers) = new_value;
+                                            custom.set(multipliers);
+                                            invalid_field.set(None);
+                                        }
+                                        _ => {
+                                            input.set_value(&value.to_string());
+                                            invalid_field.set(Some(label));
+                                        }
+                                    }
+                                }
+                            })
+                        };
+                        html! {
+                            <label>
+                                { format!("{}: ", label) }
+                                <input type="number" step="0.05" min="0" value={value.to_string()} onchange={on_change} />
+                            </label>
+                        }
+                    }) }
+                    if let Some(label) = *invalid_field {
+                        <p class="difficulty-error">{ format!("{} must be a number above 0", label) }</p>
+                    }
+                </div>
+            }
+            <button onclick={on_start}>{ "Start New Game" }</button>
+        </div>
+    }
+}
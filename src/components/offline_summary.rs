@@ -0,0 +1,31 @@
+use crate::game::OfflineProgress;
+use crate::upgrades::UpgradesConfig;
+use std::rc::Rc;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct OfflineSummaryProps {
+    pub offline_progress: Option<OfflineProgress>,
+    pub upgrades_config: Rc<UpgradesConfig>,
+}
+
+#[function_component(OfflineSummary)]
+pub fn offline_summary(props: &OfflineSummaryProps) -> Html {
+    let dismissed = use_state(|| false);
+
+    let on_dismiss = {
+        let dismissed = dismissed.clone();
+        Callback::from(move |_: MouseEvent| dismissed.set(true))
+    };
+
+    match &props.offline_progress {
+        Some(offline_progress) if !*dismissed => html! {
+            <div class="offline-summary">
+                <h2>{ "Welcome back!" }</h2>
+                <p>{ offline_progress.summary(&props.upgrades_config) }</p>
+                <button onclick={on_dismiss}>{ "Collect" }</button>
+            </div>
+        },
+        _ => html! {},
+    }
+}
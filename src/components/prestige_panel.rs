@@ -0,0 +1,40 @@
+use crate::game::{BigNum, GameAction, GameState};
+use crate::upgrades::UpgradesConfig;
+use std::rc::Rc;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct PrestigePanelProps {
+    pub state: GameState,
+    pub upgrades_config: Rc<UpgradesConfig>,
+    pub on_action: Callback<GameAction>,
+}
+
+#[function_component(PrestigePanel)]
+pub fn prestige_panel(props: &PrestigePanelProps) -> Html {
+    let prestige = &props.state.prestige;
+    let gain = props.state.prestige_gain(&props.upgrades_config);
+
+    let on_prestige = {
+        let on_action = props.on_action.clone();
+        Callback::from(move |_: MouseEvent| {
+            on_action.emit(GameAction::Prestige);
+        })
+    };
+
+    html! {
+        <div class="prestige-panel">
+            <h2>{ "Prestige" }</h2>
+            <p>{ format!("Prestige points: {}", prestige.points) }</p>
+            <p>{ format!(
+                "Permanent bonus: x{:.2}",
+                props.state.prestige_multiplier(&props.upgrades_config)
+            ) }</p>
+            <p>{ format!("Lifetime earnings: {}", prestige.lifetime_earned.floor()) }</p>
+            <p>{ format!("Prestige resets: {}", prestige.resets) }</p>
+            <button onclick={on_prestige} disabled={gain <= BigNum::ZERO}>
+                { format!("Prestige (+{} points)", gain) }
+            </button>
+        </div>
+    }
+}
@@ -0,0 +1,34 @@
+use crate::storage::SaveProblems;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct SaveProblemsNoticeProps {
+    pub save_problems: Option<SaveProblems>,
+    pub on_dismiss: Callback<()>,
+}
+
+#[function_component(SaveProblemsNotice)]
+pub fn save_problems_notice(props: &SaveProblemsNoticeProps) -> Html {
+    let on_dismiss = props.on_dismiss.reform(|_: MouseEvent| ());
+
+    match &props.save_problems {
+        Some(SaveProblems::Repaired { source, issues }) => html! {
+            <div class="save-problems">
+                <h2>{ format!("{} was repaired", source) }</h2>
+                <p>{ "Some values were invalid and have been fixed:" }</p>
+                <ul>
+                    { for issues.iter().map(|issue| html! { <li>{ issue.to_string() }</li> }) }
+                </ul>
+                <button onclick={on_dismiss}>{ "OK" }</button>
+            </div>
+        },
+        Some(SaveProblems::Failed { source, error }) => html! {
+            <div class="save-problems failed">
+                <h2>{ format!("{} could not be loaded", source) }</h2>
+                <p>{ format!("Nothing was loaded because {}.", error) }</p>
+                <button onclick={on_dismiss}>{ "OK" }</button>
+            </div>
+        },
+        None => html! {},
+    }
+}
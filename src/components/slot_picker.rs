@@ -0,0 +1,236 @@
+use crate::game::{format_duration, GameAction, GameState};
+use crate::storage::{GameStorage, SaveProblems, SaveSlots};
+use crate::upgrades::UpgradesConfig;
+use std::rc::Rc;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct SlotPickerProps {
+    pub state: GameState,
+    pub storage: GameStorage,
+    pub upgrades_config: Rc<UpgradesConfig>,
+    pub on_action: Callback<GameAction>,
+    pub on_save_problems: Callback<Option<SaveProblems>>,
+}
+
+#[function_component(SlotPicker)]
+pub fn slot_picker(props: &SlotPickerProps) -> Html {
+    let slots = use_state(SaveSlots::default);
+    let slot_name = use_state(String::new);
+    let error = use_state(|| None::<String>);
+
+    {
+        let storage = props.storage.clone();
+        let slots = slots.clone();
+        use_effect_with(storage, move |storage| {
+            let storage = storage.clone();
+            spawn_local(async move { slots.set(storage.slots().await) });
+        });
+    }
+
+    // Runs a slot operation, then shows the new slots or what went wrong.
+    let apply = {
+        let slots = slots.clone();
+        let error = error.clone();
+        Callback::from(move |result: Result<SaveSlots, String>| match result {
+            Ok(new_slots) => {
+                slots.set(new_slots);
+                error.set(None);
+            }
+            Err(message) => error.set(Some(message)),
+        })
+    };
+
+    // Puts the active slot's game in play after the active slot changed.
+    let load_active_slot = {
+        let storage = props.storage.clone();
+        let upgrades_config = props.upgrades_config.clone();
+        let on_action = props.on_action.clone();
+        let on_save_problems = props.on_save_problems.clone();
+        Callback::from(move |_: ()| {
+            let storage = storage.clone();
+            let upgrades_config = upgrades_config.clone();
+            let on_action = on_action.clone();
+            let on_save_problems = on_save_problems.clone();
+            spawn_local(async move {
+                let (state, _, problems) = storage.load(&upgrades_config).await;
+                on_action.emit(GameAction::Load(Box::new(state)));
+                on_save_problems.emit(problems);
+            });
+        })
+    };
+
+    let on_name_input = {
+        let slot_name = slot_name.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                slot_name.set(input.value());
+            }
+        })
+    };
+
+    let on_create = {
+        let storage = props.storage.clone();
+        let apply = apply.clone();
+        let slot_name = slot_name.clone();
+        Callback::from(move |_: MouseEvent| {
+            let storage = storage.clone();
+            let apply = apply.clone();
+            let slot_name = slot_name.clone();
+            spawn_local(async move {
+                let result = storage.create_slot(&slot_name).await;
+                if result.is_ok() {
+                    slot_name.set(String::new());
+                }
+                apply.emit(result);
+            });
+        })
+    };
+
+    let primary = props.upgrades_config.primary_resource();
+    let now = js_sys::Date::now();
+
+    html! {
+        <div class="slot-picker">
+            <h3>{ "Save Slots" }</h3>
+            <ul>
+                { for slots.slots.iter().map(|slot| {
+                    let active = slot.id == slots.active;
+                    // The active slot's stored metadata lags behind the game
+                    // until the next save, so describe the running game.
+                    let (amount, time_played) = if active {
+                        (props.state.resources.get(primary), props.state.statistics.time_played)
+                    } else {
+                        (slot.resources.get(primary), slot.time_played)
+                    };
+                    let details = if active {
+                        format!(
+                            "{}, played {}",
+                            props.upgrades_config.format_amount(primary, amount.floor()),
+                            format_duration(time_played)
+                        )
+                    } else if slot.is_empty() {
+                        "Empty".to_string()
+                    } else {
+                        format!(
+                            "{}, played {}, last played {} ago",
+                            props.upgrades_config.format_amount(primary, amount.floor()),
+                            format_duration(time_played),
+                            format_duration((now - slot.last_played) / 1000.0)
+                        )
+                    };
+
+                    let on_switch = {
+                        let id = slot.id.clone();
+                        let state = props.state.clone();
+                        let storage = props.storage.clone();
+                        let apply = apply.clone();
+                        let load_active_slot = load_active_slot.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            let (id, state, storage) = (id.clone(), state.clone(), storage.clone());
+                            let apply = apply.clone();
+                            let load_active_slot = load_active_slot.clone();
+                            spawn_local(async move {
+                                if let Err(error) = storage.save(&state).await {
+                                    log::error!("{}", error);
+                                }
+                                let result = storage.switch_slot(&id).await;
+                                let switched = result.is_ok();
+                                apply.emit(result);
+                                if switched {
+                                    load_active_slot.emit(());
+                                }
+                            });
+                        })
+                    };
+                    let on_rename = {
+                        let id = slot.id.clone();
+                        let storage = props.storage.clone();
+                        let apply = apply.clone();
+                        let slot_name = slot_name.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            let (id, storage) = (id.clone(), storage.clone());
+                            let apply = apply.clone();
+                            let slot_name = slot_name.clone();
+                            spawn_local(async move {
+                                let result = storage.rename_slot(&id, &slot_name).await;
+                                if result.is_ok() {
+                                    slot_name.set(String::new());
+                                }
+                                apply.emit(result);
+                            });
+                        })
+                    };
+                    let on_duplicate = {
+                        let id = slot.id.clone();
+                        let state = props.state.clone();
+                        let storage = props.storage.clone();
+                        let apply = apply.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            let (id, state, storage) = (id.clone(), state.clone(), storage.clone());
+                            let apply = apply.clone();
+                            spawn_local(async move {
+                                // Copy the game as it is now, not as of the last auto-save.
+                                if active {
+                                    if let Err(error) = storage.save(&state).await {
+                                        log::error!("{}", error);
+                                    }
+                                }
+                                apply.emit(storage.duplicate_slot(&id).await);
+                            });
+                        })
+                    };
+                    let on_delete = {
+                        let id = slot.id.clone();
+                        let storage = props.storage.clone();
+                        let apply = apply.clone();
+                        let load_active_slot = load_active_slot.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            let (id, storage) = (id.clone(), storage.clone());
+                            let apply = apply.clone();
+                            let load_active_slot = load_active_slot.clone();
+                            spawn_local(async move {
+                                let result = storage.delete_slot(&id).await;
+                                let deleted = result.is_ok();
+                                apply.emit(result);
+                                if deleted && active {
+                                    load_active_slot.emit(());
+                                }
+                            });
+                        })
+                    };
+
+                    html! {
+                        <li class={classes!("slot", active.then_some("active"))}>
+                            <strong>{ &slot.name }</strong>
+                            <p class="slot-details">{ details }</p>
+                            <div class="slot-actions">
+                                <button onclick={on_switch} disabled={active}>{ "Play" }</button>
+                                <button
+                                    onclick={on_rename}
+                                    disabled={slot_name.trim().is_empty()}
+                                    title="Rename to the name typed below"
+                                >
+                                    { "Rename" }
+                                </button>
+                                <button onclick={on_duplicate}>{ "Duplicate" }</button>
+                                <button onclick={on_delete} disabled={slots.slots.len() <= 1}>{ "Delete" }</button>
+                            </div>
+                        </li>
+                    }
+                }) }
+            </ul>
+            <input
+                type="text"
+                placeholder="Slot name"
+                value={(*slot_name).clone()}
+                oninput={on_name_input}
+            />
+            <button onclick={on_create} disabled={slot_name.trim().is_empty()}>{ "New Slot" }</button>
+            if let Some(error) = &*error {
+                <p class="slot-error">{ error }</p>
+            }
+        </div>
+    }
+}
@@ -1,45 +1,161 @@
-use yew::prelude::*;
 use crate::game::GameState;
-use crate::storage::GameStorage;
 use crate::predefined_states::load_predefined_states;
+use crate::storage::{GameStorage, LoadedSave, SaveError};
+use crate::upgrades::UpgradesConfig;
+use crate::utils::clipboard::{copy_to_clipboard, read_from_clipboard};
+use std::rc::Rc;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
 pub struct StateProps {
-    pub state: UseStateHandle<GameState>,
+    pub state: GameState,
+    pub storage: GameStorage,
+    pub upgrades_config: Rc<UpgradesConfig>,
     pub on_select_predefined_state: Callback<usize>,
+    /// A save loaded by the player, labelled with where it came from.
+    pub on_load_state: Callback<(String, Result<LoadedSave, SaveError>)>,
 }
 
 #[function_component(State)]
 pub fn state(props: &StateProps) -> Html {
-    let predefined_states = use_state(|| load_predefined_states());
+    let predefined_states = {
+        let upgrades_config = props.upgrades_config.clone();
+        use_state(move || load_predefined_states(&upgrades_config))
+    };
+
+    let save_status = use_state(|| None::<String>);
+
+    let on_save_now = {
+        let state = props.state.clone();
+        let storage = props.storage.clone();
+        let save_status = save_status.clone();
+        Callback::from(move |_| {
+            let state = state.clone();
+            let storage = storage.clone();
+            let save_status = save_status.clone();
+            spawn_local(async move {
+                save_status.set(Some(match storage.save(&state).await {
+                    Ok(()) => format!("Saved to {}", storage.backend_name()),
+                    Err(error) => error,
+                }));
+            });
+        })
+    };
 
     let on_export_state = {
         let state = props.state.clone();
         Callback::from(move |_| {
-            GameStorage::save_to_file(&*state, "game_state.json");
+            GameStorage::save_to_file(&state, "game_state.json");
         })
     };
 
     let on_import_state = {
+        let upgrades_config = props.upgrades_config.clone();
+        let on_load_state = props
+            .on_load_state
+            .reform(|result| ("Imported file".to_string(), result));
+        Callback::from(move |_| {
+            GameStorage::load_from_file(upgrades_config.clone(), on_load_state.clone());
+        })
+    };
+
+    let clipboard_status = use_state(|| None::<String>);
+    let pasted_save = use_state(String::new);
+
+    let on_copy_save_string = {
         let state = props.state.clone();
+        let clipboard_status = clipboard_status.clone();
+        Callback::from(move |_| match GameStorage::to_save_string(&state) {
+            Ok(save_string) => {
+                let clipboard_status = clipboard_status.clone();
+                copy_to_clipboard(
+                    save_string,
+                    Callback::from(move |result: Result<(), String>| {
+                        clipboard_status.set(Some(match result {
+                            Ok(()) => "Save string copied to the clipboard".to_string(),
+                            Err(error) => format!("Could not copy the save string: {}", error),
+                        }));
+                    }),
+                );
+            }
+            Err(error) => log::error!("Failed to encode save string: {}", error),
+        })
+    };
+
+    let import_save_string = {
+        let upgrades_config = props.upgrades_config.clone();
+        let on_load_state = props.on_load_state.clone();
+        Callback::from(move |(source, text): (&'static str, String)| {
+            let result = GameStorage::from_save_string(&text, &upgrades_config);
+            on_load_state.emit((source.to_string(), result));
+        })
+    };
+
+    let on_paste_from_clipboard = {
+        let import_save_string = import_save_string.clone();
+        let on_load_state = props.on_load_state.clone();
         Callback::from(move |_| {
-            GameStorage::load_from_file(state.clone());
+            let import_save_string = import_save_string.clone();
+            let on_load_state = on_load_state.clone();
+            read_from_clipboard(Callback::from(
+                move |result: Result<String, String>| match result {
+                    Ok(text) => import_save_string.emit(("Clipboard", text)),
+                    Err(error) => on_load_state
+                        .emit(("Clipboard".to_string(), Err(SaveError::Unreadable(error)))),
+                },
+            ));
         })
     };
 
+    let on_pasted_save_input = {
+        let pasted_save = pasted_save.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<web_sys::HtmlTextAreaElement>() {
+                pasted_save.set(input.value());
+            }
+        })
+    };
+
+    let on_import_pasted_save = {
+        let pasted_save = pasted_save.clone();
+        Callback::from(move |_| import_save_string.emit(("Pasted save", (*pasted_save).clone())))
+    };
+
     html! {
         <div>
             <h3>{"State Management"}</h3>
+            <p class="storage-backend">{ format!("Saving to {}", props.storage.backend_name()) }</p>
+            <button onclick={on_save_now}>{ "Save Now" }</button>
+            if let Some(status) = &*save_status {
+                <span class="save-status">{ status }</span>
+            }
             <button onclick={on_export_state}>{ "Export State" }</button>
             <button onclick={on_import_state}>{ "Import State" }</button>
+            <div class="save-string">
+                <button onclick={on_copy_save_string}>{ "Copy Save String" }</button>
+                <button onclick={on_paste_from_clipboard}>{ "Import from Clipboard" }</button>
+                if let Some(status) = &*clipboard_status {
+                    <span class="clipboard-status">{ status }</span>
+                }
+                <textarea
+                    placeholder="Or paste a save string here"
+                    value={(*pasted_save).clone()}
+                    oninput={on_pasted_save_input}
+                />
+                <button onclick={on_import_pasted_save} disabled={pasted_save.trim().is_empty()}>
+                    { "Import Save String" }
+                </button>
+            </div>
             <div>
                 <h3>{"Select Predefined State"}</h3>
                 <ul>
-                    { for predefined_states.iter().enumerate().map(|(index, _)| {
+                    { for predefined_states.iter().enumerate().map(|(index, result)| {
                         let on_select = props.on_select_predefined_state.clone();
+                        let title = result.as_ref().err().map(ToString::to_string);
                         html! {
                             <li>
-                                <button onclick={Callback::from(move |_| on_select.emit(index))}>
+                                <button {title} onclick={Callback::from(move |_| on_select.emit(index))}>
                                     { format!("State {}", index + 1) }
                                 </button>
                             </li>
@@ -49,4 +165,4 @@ pub fn state(props: &StateProps) -> Html {
             </div>
         </div>
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,58 @@
+use crate::game::{format_duration, GameState};
+use crate::upgrades::UpgradesConfig;
+use std::rc::Rc;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct StatisticsProps {
+    pub state: GameState,
+    pub upgrades_config: Rc<UpgradesConfig>,
+}
+
+#[function_component(Statistics)]
+pub fn statistics(props: &StatisticsProps) -> Html {
+    let statistics = &props.state.statistics;
+    let upgrades_config = &props.upgrades_config;
+
+    html! {
+        <div class="statistics-panel">
+            <h2>{ "Statistics" }</h2>
+            if statistics.modified {
+                <p class="modified-save">{ "This game was loaded from an edited save." }</p>
+            } else if statistics.unverified {
+                <p class="modified-save">{ "This game was loaded from an unsigned save, so it cannot be verified." }</p>
+            }
+            <p>{ format!("Time played: {}", format_duration(statistics.time_played)) }</p>
+            <p>{ format!("Current session: {}", format_duration(statistics.session_time)) }</p>
+            <p>{ format!("Longest session: {}", format_duration(statistics.longest_session)) }</p>
+            <p>{ format!("Manual clicks: {}", statistics.clicks) }</p>
+            <p>{ format!("Best clicks per second: {}", statistics.best_clicks_per_second) }</p>
+            <table>
+                <tr>
+                    <th>{ "Resource" }</th>
+                    <th>{ "Earned" }</th>
+                    <th>{ "Spent" }</th>
+                </tr>
+                { for upgrades_config.resources.iter().map(|resource| html! {
+                    <tr>
+                        <td>{ format!("{} {}", resource.icon, resource.name) }</td>
+                        <td>{ statistics.earned.get(&resource.id).floor().to_string() }</td>
+                        <td>{ statistics.spent.get(&resource.id).floor().to_string() }</td>
+                    </tr>
+                }) }
+            </table>
+            <h3>{ "Upgrades bought" }</h3>
+            <ul>
+                { for upgrades_config.upgrades.iter().map(|upgrade| {
+                    let bought = statistics.upgrades_bought.level(&upgrade.id);
+                    if bought == 0 {
+                        return html! {};
+                    }
+                    html! {
+                        <li>{ format!("{} {}: {}", upgrade.icon, upgrade.name, bought) }</li>
+                    }
+                }) }
+            </ul>
+        </div>
+    }
+}
@@ -1,53 +1,105 @@
 use yew::prelude::*;
-use crate::upgrades::UpgradesConfig;
+use crate::upgrades::{UpgradeEffect, UpgradesConfig};
 use crate::game::GameState;
 
 #[derive(Properties, PartialEq)]
 pub struct UpgradeConfigProps {
-    pub game_state: UseStateHandle<GameState>,
+    pub game_state: GameState,
     pub upgrades_config: UseStateHandle<UpgradesConfig>,
 }
 
 #[function_component(UpgradeConfig)]
 pub fn upgrade_config(props: &UpgradeConfigProps) -> Html {
     let upgrades_config = props.upgrades_config.clone();
-    let game_state = props.game_state.clone();
-
-    let auto_clicker_count = game_state.upgrades.auto_clicker;
-    let click_multiplier_count = game_state.upgrades.click_multiplier;
-
-    let auto_clicker_next_cost = game_state.get_upgrade_cost("auto_clicker");
-    let click_multiplier_next_cost = game_state.get_upgrade_cost("click_multiplier");
-
-    let auto_clicker_current_effect = auto_clicker_count;
-    let auto_clicker_next_effect = auto_clicker_count + 1;
-
-    let click_multiplier_current_effect = 1.0 + click_multiplier_count as f64 * upgrades_config.click_multiplier.multiplier.unwrap_or(1.0);
-    let click_multiplier_next_effect = 1.0 + (click_multiplier_count + 1) as f64 * upgrades_config.click_multiplier.multiplier.unwrap_or(1.0);
+    let game_state = &props.game_state;
 
     html! {
         <div class="upgrade-config">
             <h2>{ "Upgrade Configuration" }</h2>
-            <div class="upgrade">
-                <h3>{ "Auto Clicker" }</h3>
-                <p>{ format!("Base Cost: {}", upgrades_config.auto_clicker.base_cost) }</p>
-                <p>{ format!("Cost Scaling: {}", upgrades_config.auto_clicker.cost_scaling) }</p>
-                <p>{ format!("Efficiency: {}", upgrades_config.auto_clicker.efficiency.unwrap_or(0.0)) }</p>
-                <p>{ format!("Bought: {}", auto_clicker_count) }</p>
-                <p>{ format!("Next Cost: {}", auto_clicker_next_cost) }</p>
-                <p>{ format!("Current Effect: +{} clicks per second", auto_clicker_current_effect) }</p>
-                <p>{ format!("Next Effect: +{} clicks per second", auto_clicker_next_effect) }</p>
-            </div>
-            <div class="upgrade">
-                <h3>{ "Click Multiplier" }</h3>
-                <p>{ format!("Base Cost: {}", upgrades_config.click_multiplier.base_cost) }</p>
-                <p>{ format!("Cost Scaling: {}", upgrades_config.click_multiplier.cost_scaling) }</p>
-                <p>{ format!("Multiplier: {}", upgrades_config.click_multiplier.multiplier.unwrap_or(0.0)) }</p>
-                <p>{ format!("Bought: {}", click_multiplier_count) }</p>
-                <p>{ format!("Next Cost: {}", click_multiplier_next_cost) }</p>
-                <p>{ format!("Current Effect: x{:.2}", click_multiplier_current_effect) }</p>
-                <p>{ format!("Next Effect: x{:.2}", click_multiplier_next_effect) }</p>
-            </div>
+            { for upgrades_config.upgrades.iter().map(|upgrade| {
+                let count = game_state.upgrades.level(&upgrade.id);
+                let next_cost = game_state.upgrade_cost(upgrade);
+                let cost_scaling = game_state.cost_scaling_for(upgrade);
+                let cost_resource = upgrades_config.cost_resource(upgrade);
+                let next_cost = upgrades_config.format_amount(cost_resource, next_cost);
+                let formula = upgrade.effect.amount();
+                let amount = game_state.effect_amount(upgrade);
+                let effect = match &upgrade.effect {
+                    UpgradeEffect::ClickMultiplier { .. } => html! {
+                        <>
+                            <p>{ format!("Multiplier: {}", formula) }</p>
+                            <p>{ format!("Bought: {}", count) }</p>
+                            <p>{ format!("Next Cost: {}", next_cost) }</p>
+                            <p>{ format!("Current Effect: x{:.2}", 1.0 + count as f64 * amount) }</p>
+                            <p>{ format!("Next Effect: x{:.2}", 1.0 + (count + 1) as f64 * amount) }</p>
+                        </>
+                    },
+                    UpgradeEffect::AutoClicker { .. } => {
+                        let produced = upgrades_config
+                            .produced_resource(&upgrade.effect)
+                            .and_then(|id| upgrades_config.resource(id))
+                            .map(|resource| resource.name.clone())
+                            .unwrap_or_default();
+                        html! {
+                            <>
+                                <p>{ format!("Efficiency: {}", formula) }</p>
+                                <p>{ format!("Bought: {}", count) }</p>
+                                <p>{ format!("Next Cost: {}", next_cost) }</p>
+                                <p>{ format!("Current Effect: +{} {} per second", count as f64 * amount, produced) }</p>
+                                <p>{ format!("Next Effect: +{} {} per second", (count + 1) as f64 * amount, produced) }</p>
+                            </>
+                        }
+                    },
+                    UpgradeEffect::Generator { produces, .. } => {
+                        let produced = upgrades_config
+                            .get(produces)
+                            .map(|target| target.name.clone())
+                            .unwrap_or_else(|| produces.clone());
+                        html! {
+                            <>
+                                <p>{ format!("Rate: {}", formula) }</p>
+                                <p>{ format!("Bought: {}", count) }</p>
+                                <p>{ format!("Owned: {}", game_state.owned(&upgrade.id).floor()) }</p>
+                                <p>{ format!("Next Cost: {}", next_cost) }</p>
+                                <p>{ format!("Current Effect: +{} {} per second", game_state.unit_production(upgrade, &upgrades_config) * game_state.owned(&upgrade.id), produced) }</p>
+                            </>
+                        }
+                    }
+                    UpgradeEffect::Autobuyer { .. } => html! {
+                        <>
+                            <p>{ format!("Speed: {}", formula) }</p>
+                            <p>{ format!("Bought: {}", count) }</p>
+                            <p>{ format!("Next Cost: {}", next_cost) }</p>
+                            <p>{ format!("Current Effect: {} purchases per second", game_state.autobuy_rate(&upgrades_config)) }</p>
+                        </>
+                    },
+                };
+                html! {
+                    <div class="upgrade">
+                        <h3>{ format!("{} {}", upgrade.icon, upgrade.name) }</h3>
+                        <p>{ format!("Base Cost: {}", upgrades_config.format_amount(cost_resource, game_state.base_cost_for(upgrade))) }</p>
+                        <p>{ format!("Cost Scaling: {}", cost_scaling) }</p>
+                        if !upgrade.milestones.is_empty() {
+                            <p>{ match game_state.next_milestone(upgrade) {
+                                Some(milestone) => format!(
+                                    "Next milestone at {} (x{}), current bonus x{}",
+                                    milestone.level,
+                                    milestone.multiplier,
+                                    game_state.milestone_multiplier(upgrade)
+                                ),
+                                None => format!(
+                                    "All milestones reached (x{})",
+                                    game_state.milestone_multiplier(upgrade)
+                                ),
+                            } }</p>
+                        }
+                        if let Some(reason) = game_state.locked_reason(upgrade, &upgrades_config) {
+                            <p class="locked-reason">{ reason }</p>
+                        }
+                        { effect }
+                    </div>
+                }
+            }) }
         </div>
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,64 @@
+use crate::game::{BigNum, GameState};
+use crate::upgrades::{AchievementCondition, AchievementDefinition, UpgradesConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Ids of unlocked achievements. Unlocks are permanent and survive prestige.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Achievements(BTreeSet<String>);
+
+impl Achievements {
+    pub fn contains(&self, id: &str) -> bool {
+        self.0.contains(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl GameState {
+    pub fn achievement_met(&self, achievement: &AchievementDefinition) -> bool {
+        self.condition_met(&achievement.condition)
+    }
+
+    pub fn condition_met(&self, condition: &AchievementCondition) -> bool {
+        match condition {
+            AchievementCondition::Resource { resource, amount } => {
+                self.resources.get(resource) >= *amount
+            }
+            AchievementCondition::UpgradeLevel { upgrade, level } => {
+                self.upgrades.level(upgrade) >= *level
+            }
+            AchievementCondition::LifetimeEarned { amount } => {
+                self.prestige.lifetime_earned >= *amount
+            }
+            AchievementCondition::Clicks { count } => self.statistics.clicks >= *count,
+            AchievementCondition::TimePlayed { seconds } => self.statistics.time_played >= *seconds,
+        }
+    }
+
+    /// Unlocks every achievement whose condition now holds, returning the
+    /// names of the ones that were newly unlocked.
+    pub fn unlock_achievements(&mut self, upgrades_config: &UpgradesConfig) -> Vec<String> {
+        let mut unlocked = Vec::new();
+        for achievement in &upgrades_config.achievements {
+            if !self.achievements.contains(&achievement.id) && self.achievement_met(achievement) {
+                self.achievements.0.insert(achievement.id.clone());
+                unlocked.push(achievement.name.clone());
+            }
+        }
+        unlocked
+    }
+
+    /// Product of the bonuses of every unlocked achievement.
+    pub fn achievement_multiplier(&self, upgrades_config: &UpgradesConfig) -> BigNum {
+        upgrades_config
+            .achievements
+            .iter()
+            .filter(|achievement| self.achievements.contains(&achievement.id))
+            .filter_map(|achievement| achievement.bonus)
+            .fold(BigNum::ONE, |multiplier, bonus| multiplier * bonus)
+    }
+}
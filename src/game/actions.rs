@@ -1,26 +1,37 @@
-use crate::components::DevPanelAction;
-use crate::game::GameParameter;
+use crate::game::{AutobuyRule, Difficulty, GameParameter, GameState};
 
-#[derive(Clone)]
+/// How many levels a bulk purchase asks for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BuyAmount {
+    Levels(i32),
+    /// As many levels as the counter can pay for.
+    Max,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum GameAction {
     Click,
-    Reset,
+    /// Wipes all progress and starts a new game on the given difficulty.
+    Reset(Difficulty),
+    /// Resets the run in exchange for prestige points.
+    Prestige,
     UpdateGameParameter(GameParameter),
-    BuyUpgrade(String), // New action for buying upgrades
-}
-
-impl From<DevPanelAction> for GameAction {
-    fn from(action: DevPanelAction) -> Self {
-        match action {
-            DevPanelAction::UpdateBaseMultiplier(value) => {
-                GameAction::UpdateGameParameter(GameParameter::BaseMultiplier(value))
-            }
-            DevPanelAction::UpdateCostScaling(value) => {
-                GameAction::UpdateGameParameter(GameParameter::CostScaling(value))
-            }
-            DevPanelAction::UpdateAutoClickerEfficiency(value) => {
-                GameAction::UpdateGameParameter(GameParameter::AutoClickerEfficiency(value))
-            }
-        }
-    }
+    BuyUpgrade(String, BuyAmount),
+    /// Sets or, with `None`, clears the autobuyer rule for an upgrade.
+    SetAutobuyRule(String, Option<AutobuyRule>),
+    /// Starts the buff with the given id, following its stacking rule.
+    ActivateBuff(String),
+    /// Stashes the current run and starts the challenge with the given id.
+    StartChallenge(String),
+    /// Gives up on the active challenge and returns to the stashed run.
+    AbandonChallenge,
+    /// Collects the golden click currently on screen, if any.
+    CollectGoldenClick,
+    /// Advances production by the given number of seconds.
+    Tick(f64),
+    /// Replaces the whole state, e.g. from a predefined state or an imported file.
+    Load(Box<GameState>),
+    /// Re-evaluates achievements and upgrade unlocks and nothing else. Every
+    /// action does this anyway; the auto-save sends it on its own schedule.
+    CheckUnlocks,
 }
@@ -0,0 +1,234 @@
+use crate::game::{BigNum, GameState};
+use crate::upgrades::{UpgradeDefinition, UpgradeEffect, UpgradesConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Seconds of extra production `BestPayback` weighs a purchase against, so
+/// generators, whose output only shows up further down the chain, count too.
+const PAYBACK_HORIZON_SECONDS: f64 = 60.0;
+
+/// When the autobuyer buys the next level of an upgrade.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AutobuyRule {
+    /// As soon as it can be paid for.
+    WhenAffordable,
+    /// Only if at least `reserve` of the cost resource is left afterwards.
+    KeepReserve { reserve: BigNum },
+    /// Once it is the cheapest of the cheapest-first upgrades paid in the
+    /// same resource.
+    CheapestFirst,
+    /// Once no other best-payback upgrade earns back its cost sooner.
+    BestPayback,
+}
+
+impl fmt::Display for AutobuyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AutobuyRule::WhenAffordable => write!(f, "when affordable"),
+            AutobuyRule::KeepReserve { reserve } => write!(f, "keeping {} in reserve", reserve),
+            AutobuyRule::CheapestFirst => write!(f, "cheapest first"),
+            AutobuyRule::BestPayback => write!(f, "best payback"),
+        }
+    }
+}
+
+/// The player's autobuyer rules, keyed by upgrade id. Rules are kept
+/// across prestige; the autobuyer itself has to be bought again.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Automation {
+    pub rules: BTreeMap<String, AutobuyRule>,
+    /// Purchases earned but not made yet. Capped at one second's worth, so
+    /// waiting on an expensive level does not bank a burst of purchases.
+    #[serde(default)]
+    pub charge: f64,
+}
+
+/// A level bought by the autobuyer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoPurchase {
+    pub upgrade: String,
+    pub resource: String,
+    pub cost: BigNum,
+}
+
+impl GameState {
+    /// Purchases per second the autobuyer can make; zero until it is bought.
+    pub fn autobuy_rate(&self, upgrades_config: &UpgradesConfig) -> f64 {
+        upgrades_config
+            .upgrades
+            .iter()
+            .map(|upgrade| match upgrade.effect {
+                UpgradeEffect::Autobuyer { .. } => {
+                    self.upgrades.level(&upgrade.id) as f64
+                        * self.effect_amount(upgrade)
+                        * self.milestone_multiplier(upgrade)
+                }
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    pub fn set_autobuy_rule(&mut self, upgrade: &str, rule: Option<AutobuyRule>) {
+        match rule {
+            Some(rule) => self.automation.rules.insert(upgrade.to_string(), rule),
+            None => self.automation.rules.remove(upgrade),
+        };
+    }
+
+    /// Lets the autobuyer spend `seconds` worth of purchases, one level at a
+    /// time, returning what it bought.
+    pub fn run_autobuyer(
+        &mut self,
+        seconds: f64,
+        upgrades_config: &UpgradesConfig,
+    ) -> Vec<AutoPurchase> {
+        let rate = self.autobuy_rate(upgrades_config);
+        if rate <= 0.0 {
+            self.automation.charge = 0.0;
+            return Vec::new();
+        }
+        self.automation.charge = (self.automation.charge + seconds * rate).min(rate.max(1.0));
+
+        let mut purchases = Vec::new();
+        if self.automation.charge < 1.0 {
+            return purchases;
+        }
+        // Worked out once per run: a purchase only nudges them, and the next
+        // run catches up.
+        let paybacks = self.payback_times(upgrades_config);
+        while self.automation.charge >= 1.0 {
+            let Some(upgrade) = self.next_autobuy(upgrades_config, &paybacks) else {
+                break;
+            };
+            let resource = upgrades_config.cost_resource(upgrade).to_string();
+            let cost = self.bulk_upgrade_cost(upgrade, 1);
+            self.purchase(&upgrade.id, 1, &resource, cost);
+            self.automation.charge -= 1.0;
+            purchases.push(AutoPurchase {
+                upgrade: upgrade.id.clone(),
+                resource,
+                cost,
+            });
+        }
+        purchases
+    }
+
+    /// The first upgrade, in config order, whose rule says to buy it now.
+    fn next_autobuy<'a>(
+        &self,
+        upgrades_config: &'a UpgradesConfig,
+        paybacks: &BTreeMap<String, f64>,
+    ) -> Option<&'a UpgradeDefinition> {
+        let candidates: Vec<(&UpgradeDefinition, &AutobuyRule)> = upgrades_config
+            .upgrades
+            .iter()
+            .filter_map(|upgrade| {
+                let rule = self.automation.rules.get(&upgrade.id)?;
+                (self.locked_reason(upgrade, upgrades_config).is_none()
+                    && self.levels_left(upgrade) > 0)
+                    .then_some((upgrade, rule))
+            })
+            .collect();
+        let best_payback = paybacks.values().copied().fold(f64::INFINITY, f64::min);
+
+        candidates.iter().find_map(|(upgrade, rule)| {
+            let resource = upgrades_config.cost_resource(upgrade);
+            let cost = self.bulk_upgrade_cost(upgrade, 1);
+            let funds = self.resources.get(resource);
+            if cost > funds {
+                return None;
+            }
+            let ready = match rule {
+                AutobuyRule::WhenAffordable => true,
+                AutobuyRule::KeepReserve { reserve } => funds - cost >= *reserve,
+                AutobuyRule::CheapestFirst => candidates
+                    .iter()
+                    .filter(|(other, other_rule)| {
+                        **other_rule == AutobuyRule::CheapestFirst
+                            && upgrades_config.cost_resource(other) == resource
+                    })
+                    .all(|(other, _)| cost <= self.bulk_upgrade_cost(other, 1)),
+                AutobuyRule::BestPayback => paybacks
+                    .get(&upgrade.id)
+                    .is_some_and(|payback| *payback <= best_payback),
+            };
+            ready.then_some(*upgrade)
+        })
+    }
+
+    /// Seconds the extra production from one more level of each best-payback
+    /// upgrade takes to earn back its cost; infinite if it adds nothing to
+    /// its cost resource.
+    fn payback_times(&mut self, upgrades_config: &UpgradesConfig) -> BTreeMap<String, f64> {
+        let candidates: Vec<&UpgradeDefinition> = upgrades_config
+            .upgrades
+            .iter()
+            .filter(|upgrade| {
+                self.automation.rules.get(&upgrade.id) == Some(&AutobuyRule::BestPayback)
+                    && self.locked_reason(upgrade, upgrades_config).is_none()
+                    && self.levels_left(upgrade) > 0
+            })
+            .collect();
+        if candidates.is_empty() {
+            return BTreeMap::new();
+        }
+        let current = self
+            .production_over(PAYBACK_HORIZON_SECONDS, upgrades_config)
+            .resources;
+        let mut paybacks = BTreeMap::new();
+        for upgrade in candidates {
+            let resource = upgrades_config.cost_resource(upgrade);
+            // Try the extra level out in place; copying the whole state
+            // would copy a stashed challenge run too.
+            let levels = self.upgrades.clone();
+            self.upgrades.add(&upgrade.id, 1);
+            let upgraded = self
+                .production_over(PAYBACK_HORIZON_SECONDS, upgrades_config)
+                .resources
+                .get(resource);
+            self.upgrades = levels;
+            let gain = (upgraded - current.get(resource)) / PAYBACK_HORIZON_SECONDS;
+            let payback = if gain <= BigNum::ZERO {
+                f64::INFINITY
+            } else {
+                (self.bulk_upgrade_cost(upgrade, 1) / gain).to_f64()
+            };
+            paybacks.insert(upgrade.id.clone(), payback);
+        }
+        paybacks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upgrades::load_upgrades_config;
+
+    #[test]
+    fn best_payback_buys_what_earns_back_soonest() {
+        let config = load_upgrades_config();
+        let mut state = GameState::new(&config, 0);
+        state.upgrades.add("autobuyer", 1);
+        state.upgrades.add("auto_clicker", 5);
+        state.resources.add("gold", BigNum::from(10_000));
+        state.unlock_upgrades(&config);
+        // The gem mine adds no gold, so it never pays back its gold cost.
+        for upgrade in ["auto_clicker", "gem_mine"] {
+            state.set_autobuy_rule(upgrade, Some(AutobuyRule::BestPayback));
+        }
+
+        let purchases = state.run_autobuyer(1.0, &config);
+        assert!(!purchases.is_empty());
+        assert!(purchases
+            .iter()
+            .all(|purchase| purchase.upgrade == "auto_clicker"));
+        assert_eq!(
+            state.upgrades.level("auto_clicker"),
+            5 + purchases.len() as i32
+        );
+        // Trying the gem mine out left no level of it behind.
+        assert!(state.upgrades.iter().all(|(id, _)| id != "gem_mine"));
+    }
+}
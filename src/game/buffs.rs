@@ -0,0 +1,77 @@
+use crate::game::GameState;
+use crate::upgrades::{BuffDefinition, BuffStacking, BuffTarget};
+use serde::{Deserialize, Serialize};
+
+/// Buffs with less than this many seconds left count as expired, so float
+/// error never leaves a sliver of a buff behind.
+const EXPIRY_EPSILON: f64 = 1e-6;
+
+/// A temporary multiplier. Times are seconds of play (`time_played`), so a
+/// buff is paused while the game is closed and resumes with the same time
+/// remaining.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActiveBuff {
+    pub id: String,
+    pub target: BuffTarget,
+    pub multiplier: f64,
+    pub started_at: f64,
+    pub duration: f64,
+}
+
+impl ActiveBuff {
+    pub fn remaining(&self, now: f64) -> f64 {
+        (self.started_at + self.duration - now).max(0.0)
+    }
+}
+
+impl GameState {
+    /// Starts `buff`, or applies its stacking rule if it is already active.
+    pub fn activate_buff(&mut self, buff: &BuffDefinition) {
+        let now = self.statistics.time_played;
+        let existing = self.buffs.iter_mut().find(|active| active.id == buff.id);
+        match (buff.stacking, existing) {
+            (BuffStacking::Refresh, Some(active)) => {
+                active.started_at = now;
+                active.duration = buff.duration;
+            }
+            (BuffStacking::Extend, Some(active)) => {
+                active.duration += buff.duration;
+            }
+            _ => self.buffs.push(ActiveBuff {
+                id: buff.id.clone(),
+                target: buff.target,
+                multiplier: buff.multiplier,
+                started_at: now,
+                duration: buff.duration,
+            }),
+        }
+    }
+
+    /// Product of the multipliers of every active buff on `target`.
+    pub fn buff_multiplier(&self, target: BuffTarget) -> f64 {
+        self.buffs
+            .iter()
+            .filter(|buff| buff.target == target)
+            .map(|buff| buff.multiplier)
+            .product()
+    }
+
+    /// Seconds of play until the next active buff runs out.
+    pub fn next_buff_expiry(&self) -> Option<f64> {
+        let now = self.statistics.time_played;
+        self.buffs
+            .iter()
+            .map(|buff| buff.remaining(now))
+            .reduce(f64::min)
+    }
+
+    /// Drops buffs that have run out, returning them.
+    pub fn expire_buffs(&mut self) -> Vec<ActiveBuff> {
+        let now = self.statistics.time_played;
+        let (expired, active) = std::mem::take(&mut self.buffs)
+            .into_iter()
+            .partition(|buff| buff.remaining(now) < EXPIRY_EPSILON);
+        self.buffs = active;
+        expired
+    }
+}
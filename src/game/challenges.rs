@@ -0,0 +1,154 @@
+use crate::game::{BigNum, GameState};
+use crate::upgrades::{ChallengeDefinition, ChallengeModifier, UpgradesConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Challenge progress. While a challenge is active it is played in place of
+/// the normal run, which waits in `stashed_run` until the challenge ends.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Challenges {
+    /// Ids of completed challenges. Completions are permanent.
+    pub completed: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<ActiveChallenge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stashed_run: Option<Box<GameState>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActiveChallenge {
+    pub id: String,
+    /// `time_played` when the challenge started.
+    pub started_at: f64,
+    /// Copied from the config at the start, so the economy functions can
+    /// apply them without it.
+    pub modifiers: Vec<ChallengeModifier>,
+}
+
+/// How a challenge came to an end on its own.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChallengeUpdate {
+    Completed(String),
+    /// The time limit ran out before the goal was reached.
+    Failed(String),
+}
+
+impl GameState {
+    /// Stashes the current run and starts `challenge` as a fresh one.
+    /// Long-term progress comes along, as it does through a prestige.
+    pub fn start_challenge(
+        &mut self,
+        challenge: &ChallengeDefinition,
+        upgrades_config: &UpgradesConfig,
+    ) {
+        let seed = self.rng.next_u64();
+        let normal_run = std::mem::replace(self, GameState::new(upgrades_config, seed));
+        self.keep_long_term_progress(&normal_run);
+        self.challenges.active = Some(ActiveChallenge {
+            id: challenge.id.clone(),
+            started_at: self.statistics.time_played,
+            modifiers: challenge.modifiers.clone(),
+        });
+        self.challenges.stashed_run = Some(Box::new(normal_run));
+    }
+
+    /// Ends the active challenge and restores the stashed run, bringing
+    /// long-term progress back with it. Returns the id of the challenge.
+    pub fn end_challenge(
+        &mut self,
+        completed: bool,
+        upgrades_config: &UpgradesConfig,
+    ) -> Option<String> {
+        let active = self.challenges.active.take()?;
+        let seed = self.rng.next_u64();
+        let normal_run = self
+            .challenges
+            .stashed_run
+            .take()
+            .map_or_else(|| GameState::new(upgrades_config, seed), |run| *run);
+        let challenge_run = std::mem::replace(self, normal_run);
+        self.keep_long_term_progress(&challenge_run);
+        if completed {
+            self.challenges.completed.insert(active.id.clone());
+        }
+        Some(active.id)
+    }
+
+    /// Ends the active challenge once its goal is met or its time is up.
+    pub fn check_challenge(&mut self, upgrades_config: &UpgradesConfig) -> Option<ChallengeUpdate> {
+        let active = self.challenges.active.as_ref()?;
+        let challenge = upgrades_config.challenge(&active.id)?;
+        if self.condition_met(&challenge.goal) {
+            return self
+                .end_challenge(true, upgrades_config)
+                .map(ChallengeUpdate::Completed);
+        }
+        if self.challenge_time_left() == Some(0.0) {
+            return self
+                .end_challenge(false, upgrades_config)
+                .map(ChallengeUpdate::Failed);
+        }
+        None
+    }
+
+    /// Seconds of play left before the active challenge's time limit.
+    pub fn challenge_time_left(&self) -> Option<f64> {
+        let active = self.challenges.active.as_ref()?;
+        let elapsed = self.statistics.time_played - active.started_at;
+        active
+            .modifiers
+            .iter()
+            .filter_map(|modifier| match modifier {
+                ChallengeModifier::TimeLimit { seconds } => Some((seconds - elapsed).max(0.0)),
+                _ => None,
+            })
+            .reduce(f64::min)
+    }
+
+    pub fn challenge_modifiers(&self) -> &[ChallengeModifier] {
+        self.challenges
+            .active
+            .as_ref()
+            .map_or(&[], |active| &active.modifiers)
+    }
+
+    pub fn disabled_by_challenge(&self, upgrade_id: &str) -> bool {
+        self.challenge_modifiers().iter().any(|modifier| {
+            matches!(modifier, ChallengeModifier::DisabledUpgrades { upgrades }
+                if upgrades.iter().any(|id| id == upgrade_id))
+        })
+    }
+
+    /// Product of the active challenge's cost multipliers.
+    pub fn challenge_cost_multiplier(&self) -> f64 {
+        self.challenge_modifiers()
+            .iter()
+            .map(|modifier| match modifier {
+                ChallengeModifier::CostMultiplier { multiplier } => *multiplier,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    /// Product of the active challenge's production multipliers.
+    pub fn challenge_production_multiplier(&self) -> f64 {
+        self.challenge_modifiers()
+            .iter()
+            .map(|modifier| match modifier {
+                ChallengeModifier::ProductionMultiplier { multiplier } => *multiplier,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    /// Product of the bonuses of every completed challenge.
+    pub fn challenge_multiplier(&self, upgrades_config: &UpgradesConfig) -> BigNum {
+        upgrades_config
+            .challenges
+            .iter()
+            .filter(|challenge| self.challenges.completed.contains(&challenge.id))
+            .fold(BigNum::ONE, |multiplier, challenge| {
+                multiplier * challenge.bonus
+            })
+    }
+}
@@ -0,0 +1,29 @@
+/// Source of wall-clock time for the simulation, in milliseconds since the
+/// Unix epoch. Keeps `crate::game` free of browser APIs so the economy can
+/// run natively, e.g. under `cargo test` with a hand-driven clock.
+pub trait Clock {
+    fn now(&self) -> f64;
+}
+
+/// A clock that only moves when told to.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct ManualClock(std::cell::Cell<f64>);
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new(now: f64) -> Self {
+        Self(std::cell::Cell::new(now))
+    }
+
+    pub fn advance(&self, seconds: f64) {
+        self.0.set(self.0.get() + seconds * 1000.0);
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> f64 {
+        self.0.get()
+    }
+}
@@ -0,0 +1,69 @@
+use crate::game::{BigNum, GameState};
+use crate::upgrades::{DifficultyMultipliers, DifficultyPreset, UpgradeDefinition, UpgradesConfig};
+use serde::{Deserialize, Serialize};
+
+/// Preset id of a difficulty whose multipliers the player picks.
+pub const CUSTOM_DIFFICULTY: &str = "custom";
+
+/// The difficulty a game was started on. The multipliers are copied from the
+/// preset, so retuning a preset leaves games already under way alone.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Difficulty {
+    pub preset: String,
+    pub multipliers: DifficultyMultipliers,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self {
+            preset: "normal".to_string(),
+            multipliers: DifficultyMultipliers::default(),
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn from_preset(preset: &DifficultyPreset) -> Self {
+        Self {
+            preset: preset.id.clone(),
+            multipliers: preset.multipliers.clone(),
+        }
+    }
+
+    pub fn custom(multipliers: DifficultyMultipliers) -> Self {
+        Self {
+            preset: CUSTOM_DIFFICULTY.to_string(),
+            multipliers,
+        }
+    }
+
+    pub fn name(&self, upgrades_config: &UpgradesConfig) -> String {
+        match upgrades_config.difficulty(&self.preset) {
+            Some(preset) => preset.name.clone(),
+            None if self.preset == CUSTOM_DIFFICULTY => "Custom".to_string(),
+            None => self.preset.clone(),
+        }
+    }
+}
+
+impl GameState {
+    /// `base_multiplier` with the difficulty applied.
+    pub fn effective_base_multiplier(&self) -> f64 {
+        self.base_multiplier * self.difficulty.multipliers.base_multiplier
+    }
+
+    /// `auto_clicker_efficiency` with the difficulty applied.
+    pub fn effective_auto_clicker_efficiency(&self) -> f64 {
+        self.auto_clicker_efficiency * self.difficulty.multipliers.auto_clicker_efficiency
+    }
+
+    /// `upgrade`'s base cost with the difficulty applied.
+    pub fn base_cost_for(&self, upgrade: &UpgradeDefinition) -> BigNum {
+        upgrade.base_cost * self.difficulty.multipliers.base_cost
+    }
+
+    /// The offline efficiency with the difficulty applied.
+    pub fn offline_efficiency(&self, upgrades_config: &UpgradesConfig) -> f64 {
+        upgrades_config.offline.efficiency * self.difficulty.multipliers.offline_efficiency
+    }
+}
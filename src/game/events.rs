@@ -0,0 +1,188 @@
+use crate::game::{AutobuyRule, BigNum, GameParameter};
+use crate::upgrades::GoldenClickReward;
+use std::fmt;
+
+/// Outcome of reducing a [`GameAction`](crate::game::GameAction), for the log
+/// panel and anything else that reacts to what happened.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    Clicked {
+        value: BigNum,
+        critical: bool,
+    },
+    Purchased {
+        upgrade: String,
+        levels: i32,
+        resource: String,
+        cost: BigNum,
+    },
+    AutoPurchased {
+        upgrade: String,
+        resource: String,
+        cost: BigNum,
+    },
+    AutobuyRuleChanged {
+        upgrade: String,
+        rule: Option<AutobuyRule>,
+    },
+    InsufficientFunds {
+        upgrade: String,
+        resource: String,
+        cost: BigNum,
+    },
+    UnknownUpgrade {
+        upgrade: String,
+    },
+    UpgradeLocked {
+        upgrade: String,
+        reason: String,
+    },
+    /// The upgrade is already at [`MAX_UPGRADE_LEVEL`](crate::game::MAX_UPGRADE_LEVEL).
+    UpgradeMaxed {
+        upgrade: String,
+    },
+    UpgradeUnlocked {
+        name: String,
+    },
+    ParameterUpdated(GameParameter),
+    Loaded,
+    Reset,
+    Prestiged {
+        gained: BigNum,
+    },
+    PrestigeUnavailable,
+    AchievementUnlocked {
+        name: String,
+    },
+    BuffActivated {
+        name: String,
+    },
+    BuffExpired {
+        name: String,
+    },
+    UnknownBuff {
+        buff: String,
+    },
+    ChallengeStarted {
+        name: String,
+    },
+    ChallengeCompleted {
+        name: String,
+        bonus: f64,
+    },
+    ChallengeFailed {
+        name: String,
+    },
+    ChallengeAbandoned {
+        name: String,
+    },
+    /// A challenge was started while another one was still active.
+    ChallengeInProgress,
+    UnknownChallenge {
+        challenge: String,
+    },
+    GoldenClickSpawned,
+    GoldenClickMissed,
+    GoldenClickCollected {
+        reward: GoldenClickReward,
+    },
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameEvent::Clicked {
+                value,
+                critical: false,
+            } => write!(f, "Clicked (+{})", value),
+            GameEvent::Clicked {
+                value,
+                critical: true,
+            } => write!(f, "Critical click! (+{})", value),
+            GameEvent::Purchased {
+                upgrade,
+                levels: 1,
+                resource,
+                cost,
+            } => write!(f, "Bought upgrade: {} for {} {}", upgrade, cost, resource),
+            GameEvent::Purchased {
+                upgrade,
+                levels,
+                resource,
+                cost,
+            } => write!(
+                f,
+                "Bought upgrade: {} x{} for {} {}",
+                upgrade, levels, cost, resource
+            ),
+            GameEvent::AutoPurchased {
+                upgrade,
+                resource,
+                cost,
+            } => write!(f, "Autobuyer bought: {} for {} {}", upgrade, cost, resource),
+            GameEvent::AutobuyRuleChanged {
+                upgrade,
+                rule: Some(rule),
+            } => write!(f, "Autobuying {} {}", upgrade, rule),
+            GameEvent::AutobuyRuleChanged {
+                upgrade,
+                rule: None,
+            } => write!(f, "Stopped autobuying {}", upgrade),
+            GameEvent::InsufficientFunds {
+                upgrade,
+                resource,
+                cost,
+            } => write!(
+                f,
+                "Not enough {} for {} (needs {})",
+                resource, upgrade, cost
+            ),
+            GameEvent::UnknownUpgrade { upgrade } => write!(f, "Unknown upgrade: {}", upgrade),
+            GameEvent::UpgradeLocked { upgrade, reason } => {
+                write!(f, "{} is locked. {}", upgrade, reason)
+            }
+            GameEvent::UpgradeMaxed { upgrade } => {
+                write!(f, "{} is already at its maximum level", upgrade)
+            }
+            GameEvent::UpgradeUnlocked { name } => write!(f, "New upgrade available: {}", name),
+            GameEvent::ParameterUpdated(param) => write!(f, "Updated parameter: {:?}", param),
+            GameEvent::Loaded => write!(f, "Game state loaded"),
+            GameEvent::Reset => write!(f, "Game reset"),
+            GameEvent::Prestiged { gained } => {
+                write!(f, "Prestiged for {} prestige points", gained)
+            }
+            GameEvent::PrestigeUnavailable => {
+                write!(f, "Not enough lifetime earnings to prestige yet")
+            }
+            GameEvent::AchievementUnlocked { name } => write!(f, "Achievement unlocked: {}", name),
+            GameEvent::BuffActivated { name } => write!(f, "Buff started: {}", name),
+            GameEvent::BuffExpired { name } => write!(f, "Buff ended: {}", name),
+            GameEvent::UnknownBuff { buff } => write!(f, "Unknown buff: {}", buff),
+            GameEvent::ChallengeStarted { name } => write!(f, "Challenge started: {}", name),
+            GameEvent::ChallengeCompleted { name, bonus } => {
+                write!(f, "Challenge completed: {} (permanent x{})", name, bonus)
+            }
+            GameEvent::ChallengeFailed { name } => {
+                write!(f, "Challenge failed: {} ran out of time", name)
+            }
+            GameEvent::ChallengeAbandoned { name } => write!(f, "Challenge abandoned: {}", name),
+            GameEvent::ChallengeInProgress => {
+                write!(f, "Finish or abandon the current challenge first")
+            }
+            GameEvent::UnknownChallenge { challenge } => {
+                write!(f, "Unknown challenge: {}", challenge)
+            }
+            GameEvent::GoldenClickSpawned => write!(f, "A golden click appeared!"),
+            GameEvent::GoldenClickMissed => write!(f, "The golden click faded away"),
+            GameEvent::GoldenClickCollected { reward } => match reward {
+                GoldenClickReward::ProductionSeconds { seconds } => {
+                    write!(f, "Golden click: {} seconds of production", seconds)
+                }
+                GoldenClickReward::Resource { resource, amount } => {
+                    write!(f, "Golden click: {} {}", amount, resource)
+                }
+                GoldenClickReward::Buff { buff } => write!(f, "Golden click: {} buff", buff),
+            },
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::game::GameState;
+use crate::upgrades::{Milestone, UpgradeDefinition};
+
+impl GameState {
+    /// Product of the multipliers of every milestone `upgrade` has reached.
+    pub fn milestone_multiplier(&self, upgrade: &UpgradeDefinition) -> f64 {
+        let level = self.upgrades.level(&upgrade.id);
+        upgrade
+            .milestones
+            .iter()
+            .filter(|milestone| milestone.level <= level)
+            .map(|milestone| milestone.multiplier)
+            .product()
+    }
+
+    /// The lowest milestone of `upgrade` not reached yet.
+    pub fn next_milestone<'a>(&self, upgrade: &'a UpgradeDefinition) -> Option<&'a Milestone> {
+        let level = self.upgrades.level(&upgrade.id);
+        upgrade
+            .milestones
+            .iter()
+            .filter(|milestone| milestone.level > level)
+            .min_by_key(|milestone| milestone.level)
+    }
+}
@@ -1,5 +1,40 @@
+pub mod achievements;
 pub mod actions;
+pub mod automation;
+pub mod buffs;
+pub mod challenges;
+pub mod clock;
+pub mod difficulty;
+pub mod events;
+pub mod generators;
+pub mod milestones;
+pub mod number;
+pub mod offline;
+pub mod prestige;
+pub mod random;
+pub mod random_events;
+pub mod reducer;
+pub mod sanitize;
 pub mod state;
+pub mod statistics;
+pub mod unlocks;
 
+pub use achievements::*;
 pub use actions::*;
-pub use state::*;
\ No newline at end of file
+pub use automation::*;
+pub use buffs::*;
+pub use challenges::*;
+pub use clock::*;
+pub use difficulty::*;
+pub use events::*;
+pub use generators::*;
+pub use number::*;
+pub use offline::*;
+pub use prestige::*;
+pub use random::*;
+pub use random_events::*;
+pub use reducer::*;
+pub use sanitize::*;
+pub use state::*;
+pub use statistics::*;
+pub use unlocks::*;
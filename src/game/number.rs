@@ -0,0 +1,479 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Beyond this many orders of magnitude apart, the smaller operand of an
+/// addition no longer shows up in an f64 mantissa.
+const MAX_SIGNIFICANT_DIGITS: i64 = 17;
+
+/// Arbitrary-magnitude number stored as `mantissa * 10^exponent`.
+///
+/// The mantissa is kept normalized to `1 <= |mantissa| < 10` (or exactly 0),
+/// so values far past `f64::MAX` keep about 15 significant digits.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BigNum {
+    mantissa: f64,
+    exponent: i64,
+}
+
+impl BigNum {
+    pub const ZERO: BigNum = BigNum {
+        mantissa: 0.0,
+        exponent: 0,
+    };
+    pub const ONE: BigNum = BigNum {
+        mantissa: 1.0,
+        exponent: 0,
+    };
+
+    pub fn new(mantissa: f64, exponent: i64) -> Self {
+        if mantissa == 0.0 {
+            return Self::ZERO;
+        }
+        if !mantissa.is_finite() {
+            return Self {
+                mantissa,
+                exponent: 0,
+            };
+        }
+        let shift = mantissa.abs().log10().floor();
+        let mut mantissa = mantissa / 10f64.powf(shift);
+        let mut exponent = exponent.saturating_add(shift as i64);
+        // Guard against the division landing just outside [1, 10).
+        if mantissa.abs() >= 10.0 {
+            mantissa /= 10.0;
+            exponent += 1;
+        } else if mantissa.abs() < 1.0 {
+            mantissa *= 10.0;
+            exponent -= 1;
+        }
+        Self { mantissa, exponent }
+    }
+
+    pub fn from_f64(value: f64) -> Self {
+        Self::new(value, 0)
+    }
+
+    /// Builds `10^log10`, the inverse of [`BigNum::log10`].
+    pub fn from_log10(log10: f64) -> Self {
+        if log10 == f64::NEG_INFINITY {
+            return Self::ZERO;
+        }
+        if !log10.is_finite() {
+            return Self::from_f64(f64::powf(10.0, log10));
+        }
+        let exponent = log10.floor();
+        Self::new(10f64.powf(log10 - exponent), exponent as i64)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0.0
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.mantissa.is_finite()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa < 0.0
+    }
+
+    /// Converts to an f64, saturating to infinity past `f64::MAX`.
+    pub fn to_f64(self) -> f64 {
+        if self.exponent > 308 {
+            self.mantissa * f64::INFINITY
+        } else if self.exponent < -324 {
+            0.0
+        } else {
+            self.mantissa * 10f64.powi(self.exponent as i32)
+        }
+    }
+
+    pub fn log10(&self) -> f64 {
+        self.exponent as f64 + self.mantissa.abs().log10()
+    }
+
+    pub fn powf(self, power: f64) -> Self {
+        if self.is_zero() {
+            return if power == 0.0 { Self::ONE } else { Self::ZERO };
+        }
+        if self.is_negative() {
+            return Self::from_f64(self.to_f64().powf(power));
+        }
+        Self::from_log10(self.log10() * power)
+    }
+
+    pub fn floor(self) -> Self {
+        // Past 15 digits the value has no fractional part left to drop.
+        if self.exponent >= 15 || !self.is_finite() {
+            self
+        } else {
+            Self::from_f64(self.to_f64().floor())
+        }
+    }
+
+    pub fn round(self) -> Self {
+        if self.exponent >= 15 || !self.is_finite() {
+            self
+        } else {
+            Self::from_f64(self.to_f64().round())
+        }
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        if other > self {
+            other
+        } else {
+            self
+        }
+    }
+
+    pub fn min(self, other: Self) -> Self {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn sign(&self) -> f64 {
+        if self.is_zero() {
+            0.0
+        } else {
+            self.mantissa.signum()
+        }
+    }
+}
+
+impl Default for BigNum {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl From<f64> for BigNum {
+    fn from(value: f64) -> Self {
+        Self::from_f64(value)
+    }
+}
+
+impl From<i32> for BigNum {
+    fn from(value: i32) -> Self {
+        Self::from_f64(value as f64)
+    }
+}
+
+impl Add for BigNum {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        if self.is_zero() {
+            return rhs;
+        }
+        if rhs.is_zero() {
+            return self;
+        }
+        if !self.is_finite() || !rhs.is_finite() {
+            return Self::from_f64(self.to_f64() + rhs.to_f64());
+        }
+        let (big, small) = if self.exponent >= rhs.exponent {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+        let difference = big.exponent - small.exponent;
+        if difference > MAX_SIGNIFICANT_DIGITS {
+            return big;
+        }
+        Self::new(
+            big.mantissa + small.mantissa / 10f64.powi(difference as i32),
+            big.exponent,
+        )
+    }
+}
+
+impl Sub for BigNum {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl Neg for BigNum {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            mantissa: -self.mantissa,
+            exponent: self.exponent,
+        }
+    }
+}
+
+impl Mul for BigNum {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.mantissa * rhs.mantissa,
+            self.exponent.saturating_add(rhs.exponent),
+        )
+    }
+}
+
+impl Mul<f64> for BigNum {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        self * Self::from_f64(rhs)
+    }
+}
+
+impl Div for BigNum {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        Self::new(
+            self.mantissa / rhs.mantissa,
+            self.exponent.saturating_sub(rhs.exponent),
+        )
+    }
+}
+
+impl Div<f64> for BigNum {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self {
+        self / Self::from_f64(rhs)
+    }
+}
+
+impl AddAssign for BigNum {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for BigNum {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for BigNum {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Sum for BigNum {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl PartialOrd for BigNum {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if !self.is_finite() || !other.is_finite() {
+            return self.to_f64().partial_cmp(&other.to_f64());
+        }
+        let sign = self.sign();
+        match sign.partial_cmp(&other.sign()) {
+            Some(Ordering::Equal) if sign != 0.0 => {}
+            ordering => return ordering,
+        }
+        let magnitude = self
+            .exponent
+            .cmp(&other.exponent)
+            .then(self.mantissa.abs().total_cmp(&other.mantissa.abs()));
+        Some(if sign > 0.0 {
+            magnitude
+        } else {
+            magnitude.reverse()
+        })
+    }
+}
+
+impl fmt::Display for BigNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.is_finite() {
+            return write!(f, "{}", self.mantissa);
+        }
+        if self.exponent.abs() < 6 {
+            let value = self.to_f64();
+            match f.precision() {
+                Some(precision) => write!(f, "{:.*}", precision, value),
+                None => {
+                    let formatted = format!("{:.2}", value);
+                    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+                    write!(f, "{}", trimmed)
+                }
+            }
+        } else {
+            let precision = f.precision().unwrap_or(2);
+            write!(f, "{:.*}e{}", precision, self.mantissa, self.exponent)
+        }
+    }
+}
+
+impl FromStr for BigNum {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = |e: &dyn fmt::Display| format!("Invalid number '{}': {}", s, e);
+        match s.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => {
+                let mantissa = mantissa.parse::<f64>().map_err(|e| invalid(&e))?;
+                let exponent = exponent.parse::<i64>().map_err(|e| invalid(&e))?;
+                Ok(Self::new(mantissa, exponent))
+            }
+            None => s
+                .parse::<f64>()
+                .map(Self::from_f64)
+                .map_err(|e| invalid(&e)),
+        }
+    }
+}
+
+/// Encoded as a `"<mantissa>e<exponent>"` string so the JSON form never
+/// loses range; plain JSON numbers are accepted on the way in.
+impl Serialize for BigNum {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_zero() || !self.is_finite() {
+            serializer.serialize_str(&self.mantissa.to_string())
+        } else {
+            serializer.serialize_str(&format!("{}e{}", self.mantissa, self.exponent))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BigNum {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BigNumVisitor;
+
+        impl Visitor<'_> for BigNumVisitor {
+            type Value = BigNum;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a number or a \"<mantissa>e<exponent>\" string")
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<BigNum, E> {
+                Ok(BigNum::from_f64(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<BigNum, E> {
+                Ok(BigNum::from_f64(value as f64))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<BigNum, E> {
+                Ok(BigNum::from_f64(value as f64))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<BigNum, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(BigNumVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(value: BigNum) -> String {
+        serde_json::to_string(&value).unwrap()
+    }
+
+    fn from_json(json: &str) -> BigNum {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn normalizes_the_mantissa() {
+        assert_eq!(BigNum::new(1500.0, 0), BigNum::new(1.5, 3));
+        assert_eq!(BigNum::new(0.025, 2), BigNum::new(2.5, 0));
+        assert_eq!(BigNum::new(0.0, 40), BigNum::ZERO);
+        assert_eq!(json(BigNum::from(1500)), r#""1.5e3""#);
+    }
+
+    #[test]
+    fn does_arithmetic() {
+        let a = BigNum::from(2000);
+        let b = BigNum::from(500);
+        assert_eq!(a + b, BigNum::from(2500));
+        assert_eq!(a - b, BigNum::from(1500));
+        assert_eq!(a * b, BigNum::from(1_000_000));
+        assert_eq!(a / b, BigNum::from(4));
+        assert_eq!([a, b, b].into_iter().sum::<BigNum>(), BigNum::from(3000));
+        // Too small to register next to the larger operand.
+        let huge = BigNum::new(1.0, 40);
+        assert_eq!(huge + BigNum::ONE, huge);
+    }
+
+    #[test]
+    fn goes_past_f64() {
+        let value = BigNum::new(1.0, 200) * BigNum::new(2.5, 200);
+        assert_eq!(value, BigNum::new(2.5, 400));
+        assert_eq!(value.to_f64(), f64::INFINITY);
+        assert!((value.log10() - (400.0 + 2.5_f64.log10())).abs() < 1e-9);
+        assert_eq!(json(value), r#""2.5e400""#);
+        assert_eq!(from_json(&json(value)), value);
+        assert_eq!(value / BigNum::new(1.0, 398), BigNum::from(250));
+    }
+
+    #[test]
+    fn orders_by_sign_then_magnitude() {
+        let mut values = vec![
+            BigNum::new(1.0, 400),
+            BigNum::from(-5),
+            BigNum::ZERO,
+            BigNum::new(9.0, 399),
+            BigNum::new(-1.0, 400),
+            BigNum::from(2),
+        ];
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            values,
+            vec![
+                BigNum::new(-1.0, 400),
+                BigNum::from(-5),
+                BigNum::ZERO,
+                BigNum::from(2),
+                BigNum::new(9.0, 399),
+                BigNum::new(1.0, 400),
+            ]
+        );
+        assert_eq!(BigNum::from(3).max(BigNum::from(7)), BigNum::from(7));
+        assert_eq!(BigNum::from(3).min(BigNum::from(-7)), BigNum::from(-7));
+    }
+
+    #[test]
+    fn handles_zero_and_negatives() {
+        assert_eq!(BigNum::from(5) - BigNum::from(8), BigNum::from(-3));
+        assert!((BigNum::from(5) - BigNum::from(8)).is_negative());
+        assert!((BigNum::from(4) - BigNum::from(4)).is_zero());
+        assert_eq!(json(BigNum::ZERO), r#""0""#);
+        assert_eq!(from_json(&json(BigNum::ZERO)), BigNum::ZERO);
+        let negative = BigNum::new(-2.5, 50);
+        assert_eq!(json(negative), r#""-2.5e50""#);
+        assert_eq!(from_json(&json(negative)), negative);
+    }
+
+    #[test]
+    fn reads_plain_numbers_and_rejects_garbage() {
+        assert_eq!(from_json("42"), BigNum::from(42));
+        assert_eq!(from_json("1.5"), BigNum::from(1.5));
+        assert_eq!(from_json(r#""3E2""#), BigNum::from(300));
+        assert!("1.5e".parse::<BigNum>().is_err());
+        assert!("gold".parse::<BigNum>().is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::game::{Clock, GameState, Resources};
+use crate::upgrades::UpgradesConfig;
+
+/// What a returning player was credited for the time the game was closed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfflineProgress {
+    pub elapsed_seconds: f64,
+    pub credited_seconds: f64,
+    pub efficiency: f64,
+    pub earned: Resources,
+}
+
+impl OfflineProgress {
+    pub fn summary(&self, upgrades_config: &UpgradesConfig) -> String {
+        let earned = self
+            .earned
+            .iter()
+            .map(|(resource, amount)| upgrades_config.format_amount(resource, amount.floor()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut summary = format!(
+            "While you were away for {}, you earned {} ({:.0}% efficiency).",
+            format_duration(self.elapsed_seconds),
+            earned,
+            self.efficiency * 100.0
+        );
+        if self.credited_seconds < self.elapsed_seconds {
+            summary.push_str(&format!(
+                " Only the first {} counted.",
+                format_duration(self.credited_seconds)
+            ));
+        }
+        summary
+    }
+}
+
+impl GameState {
+    /// Credits production for the time between `last_saved` and the clock's
+    /// current time, then moves `last_saved` up to it.
+    pub fn apply_offline_progress(
+        &mut self,
+        clock: &(impl Clock + ?Sized),
+        upgrades_config: &UpgradesConfig,
+    ) -> Option<OfflineProgress> {
+        let now = clock.now();
+        // Predefined states carry no real save time.
+        if self.last_saved <= 0.0 {
+            self.last_saved = now;
+            return None;
+        }
+
+        let elapsed_seconds = ((now - self.last_saved) / 1000.0).max(0.0);
+        let credited_seconds = elapsed_seconds.min(upgrades_config.offline.max_seconds);
+        let efficiency = self.offline_efficiency(upgrades_config);
+        self.last_saved = now;
+
+        // Buffs are paused while away, so they boost none of it either.
+        let buffs = std::mem::take(&mut self.buffs);
+        let production = self
+            .production_over(credited_seconds, upgrades_config)
+            .scaled(efficiency);
+        self.buffs = buffs;
+        if production.resources.is_empty() {
+            return None;
+        }
+        self.collect(&production, upgrades_config);
+        Some(OfflineProgress {
+            elapsed_seconds,
+            credited_seconds,
+            efficiency,
+            earned: production.resources,
+        })
+    }
+}
+
+/// Formats seconds as e.g. `2h 5m`, `3m 20s` or `45s`.
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.floor() as u64;
+    let (hours, minutes, seconds) = (total / 3600, total % 3600 / 60, total % 60);
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BigNum, ManualClock};
+    use crate::upgrades::load_upgrades_config;
+
+    const SAVED_AT: f64 = 1_000_000.0;
+
+    fn assert_close(actual: BigNum, expected: BigNum) {
+        let error = ((actual - expected) / expected).to_f64().abs();
+        assert!(error < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    fn saved_game(upgrades_config: &UpgradesConfig) -> GameState {
+        let mut state = GameState::new(upgrades_config, 0);
+        state.upgrades.add("auto_clicker", 2);
+        state.last_saved = SAVED_AT;
+        state
+    }
+
+    #[test]
+    fn credits_offline_time_at_reduced_efficiency() {
+        let config = load_upgrades_config();
+        let mut state = saved_game(&config);
+        let rate = state.calculate_clicks_per_second(&config);
+        let efficiency = state.offline_efficiency(&config);
+        assert!(efficiency < 1.0);
+        let clock = ManualClock::new(SAVED_AT);
+        clock.advance(100.0);
+
+        let progress = state.apply_offline_progress(&clock, &config).unwrap();
+        assert_eq!(progress.elapsed_seconds, 100.0);
+        assert_eq!(progress.credited_seconds, 100.0);
+        assert_eq!(progress.efficiency, efficiency);
+        assert_close(progress.earned.get("gold"), rate * 100.0 * efficiency);
+        assert_eq!(progress.earned.get("gold"), state.resources.get("gold"));
+        assert_eq!(state.last_saved, clock.now());
+    }
+
+    #[test]
+    fn caps_credited_time() {
+        let config = load_upgrades_config();
+        let mut state = saved_game(&config);
+        let rate = state.calculate_clicks_per_second(&config);
+        let clock = ManualClock::new(SAVED_AT);
+        clock.advance(config.offline.max_seconds * 3.0);
+
+        let progress = state.apply_offline_progress(&clock, &config).unwrap();
+        assert_eq!(progress.elapsed_seconds, config.offline.max_seconds * 3.0);
+        assert_eq!(progress.credited_seconds, config.offline.max_seconds);
+        assert_close(
+            state.resources.get("gold"),
+            rate * config.offline.max_seconds * progress.efficiency,
+        );
+    }
+
+    #[test]
+    fn pauses_buffs_while_away() {
+        let config = load_upgrades_config();
+        let mut state = saved_game(&config);
+        let rate = state.calculate_clicks_per_second(&config);
+        state.activate_buff(config.buff("frenzy").unwrap());
+        let buffs = state.buffs.clone();
+        let clock = ManualClock::new(SAVED_AT);
+        clock.advance(100.0);
+
+        let progress = state.apply_offline_progress(&clock, &config).unwrap();
+        assert_close(
+            state.resources.get("gold"),
+            rate * 100.0 * progress.efficiency,
+        );
+        assert_eq!(state.buffs, buffs);
+    }
+
+    #[test]
+    fn credits_nothing_to_a_game_never_saved() {
+        let config = load_upgrades_config();
+        let mut state = saved_game(&config);
+        state.last_saved = 0.0;
+        let clock = ManualClock::new(SAVED_AT);
+
+        assert!(state.apply_offline_progress(&clock, &config).is_none());
+        assert!(state.resources.is_empty());
+        assert_eq!(state.last_saved, SAVED_AT);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::game::{BigNum, GameState};
+use crate::upgrades::UpgradesConfig;
+use serde::{Deserialize, Serialize};
+
+/// Progress that outlives a prestige reset.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct PrestigeState {
+    /// Meta-currency earned so far; each point is a permanent bonus.
+    pub points: BigNum,
+    /// Resources earned across every run, including the current one.
+    pub lifetime_earned: BigNum,
+    pub resets: u32,
+}
+
+impl GameState {
+    /// Points a prestige reset would grant right now:
+    /// `floor((lifetime_earned / divisor) ^ exponent)` minus points already owned.
+    pub fn prestige_gain(&self, upgrades_config: &UpgradesConfig) -> BigNum {
+        let prestige_config = &upgrades_config.prestige;
+        let total = (self.prestige.lifetime_earned / prestige_config.divisor)
+            .powf(prestige_config.exponent)
+            .floor();
+        (total - self.prestige.points).max(BigNum::ZERO)
+    }
+
+    /// Permanent multiplier from prestige points, applied to clicks and production.
+    pub fn prestige_multiplier(&self, upgrades_config: &UpgradesConfig) -> BigNum {
+        BigNum::ONE + self.prestige.points * upgrades_config.prestige.bonus_per_point
+    }
+
+    /// Starts a fresh run, keeping long-term progress and adding the
+    /// pending gain.
+    pub fn prestige(&mut self, upgrades_config: &UpgradesConfig) -> BigNum {
+        let gained = self.prestige_gain(upgrades_config);
+        let seed = self.rng.next_u64();
+        let previous_run = std::mem::replace(self, GameState::new(upgrades_config, seed));
+        self.keep_long_term_progress(&previous_run);
+        self.prestige.points += gained;
+        self.prestige.resets += 1;
+        gained
+    }
+
+    /// Copies what outlives a run from `previous_run`: prestige progress,
+    /// achievements, completed challenges, statistics, autobuyer rules and
+    /// the difficulty.
+    pub fn keep_long_term_progress(&mut self, previous_run: &GameState) {
+        self.prestige = previous_run.prestige.clone();
+        self.achievements = previous_run.achievements.clone();
+        self.challenges.completed = previous_run.challenges.completed.clone();
+        self.statistics = previous_run.statistics.clone();
+        self.automation.rules = previous_run.automation.rules.clone();
+        self.difficulty = previous_run.difficulty.clone();
+    }
+}
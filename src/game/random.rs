@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+/// Seed used by states that carry none, such as predefined states.
+const DEFAULT_SEED: u64 = 0x5eed_1d1e_c11c_4e55;
+
+/// SplitMix64 generator. Its whole state is one `u64` that lives in
+/// `GameState`, so the same seed and actions replay the same events.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill an f64 mantissa exactly.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// True with probability `probability`.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self(DEFAULT_SEED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_splitmix64() {
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(rng.next_u64(), 0x06c4_5d18_8009_454f);
+    }
+
+    #[test]
+    fn replays_from_the_same_seed() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let a: Vec<u64> = (0..100).map(|_| a.next_u64()).collect();
+        let b: Vec<u64> = (0..100).map(|_| b.next_u64()).collect();
+        assert_eq!(a, b);
+        assert_ne!(Rng::new(43).next_u64(), a[0]);
+    }
+
+    #[test]
+    fn draws_fractions_and_chances() {
+        let mut rng = Rng::default();
+        assert!((0..1000).all(|_| (0.0..1.0).contains(&rng.next_f64())));
+        assert!((0..1000).all(|_| !rng.chance(0.0)));
+        assert!((0..1000).all(|_| rng.chance(1.0)));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::game::{BigNum, GameState};
+use crate::upgrades::{GoldenClickReward, UpgradesConfig, WeightedReward};
+use serde::{Deserialize, Serialize};
+
+/// A golden click waiting to be collected. `expires_at` is in seconds of
+/// play, like buff timers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GoldenClick {
+    pub expires_at: f64,
+    /// Where it shows up, as fractions of the play area's width and height.
+    pub x: f64,
+    pub y: f64,
+}
+
+/// What a tick did to the golden click.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GoldenClickUpdate {
+    Spawned,
+    Missed,
+}
+
+impl GameState {
+    /// Rolls whether the next manual click is critical, returning the extra
+    /// multiplier if it is.
+    pub fn roll_critical(&mut self, upgrades_config: &UpgradesConfig) -> Option<f64> {
+        let critical_click = &upgrades_config.random_events.critical_click;
+        (critical_click.chance > 0.0 && self.rng.chance(critical_click.chance))
+            .then_some(critical_click.multiplier)
+    }
+
+    /// Expires the golden click once its time is up, or rolls for a new one
+    /// to spawn over the last `seconds`. Call after `tick`.
+    pub fn advance_golden_click(
+        &mut self,
+        seconds: f64,
+        upgrades_config: &UpgradesConfig,
+    ) -> Option<GoldenClickUpdate> {
+        let now = self.statistics.time_played;
+        match &self.golden_click {
+            Some(golden_click) if golden_click.expires_at <= now => {
+                self.golden_click = None;
+                Some(GoldenClickUpdate::Missed)
+            }
+            Some(_) => None,
+            None => {
+                let golden_click = &upgrades_config.random_events.golden_click;
+                // Chance that at least one spawn happens within `seconds`.
+                let probability =
+                    1.0 - (1.0 - golden_click.spawn_chance_per_second).powf(seconds.max(0.0));
+                if probability > 0.0 && self.rng.chance(probability) {
+                    self.golden_click = Some(GoldenClick {
+                        expires_at: now + golden_click.lifetime,
+                        x: self.rng.next_f64(),
+                        y: self.rng.next_f64(),
+                    });
+                    Some(GoldenClickUpdate::Spawned)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Collects the golden click if there is one, granting and returning a
+    /// reward picked by weight.
+    pub fn collect_golden_click(
+        &mut self,
+        upgrades_config: &UpgradesConfig,
+    ) -> Option<GoldenClickReward> {
+        self.golden_click.take()?;
+        let reward = self
+            .pick_reward(&upgrades_config.random_events.golden_click.rewards)?
+            .clone();
+        match &reward {
+            GoldenClickReward::ProductionSeconds { seconds } => {
+                for resource in &upgrades_config.resources {
+                    let amount =
+                        self.production_per_second(&resource.id, upgrades_config) * *seconds;
+                    if amount > BigNum::ZERO {
+                        self.earn(&resource.id, amount, upgrades_config);
+                    }
+                }
+            }
+            GoldenClickReward::Resource { resource, amount } => {
+                self.earn(resource, *amount, upgrades_config);
+            }
+            GoldenClickReward::Buff { buff } => {
+                if let Some(definition) = upgrades_config.buff(buff) {
+                    self.activate_buff(definition);
+                }
+            }
+        }
+        Some(reward)
+    }
+
+    fn pick_reward<'a>(&mut self, rewards: &'a [WeightedReward]) -> Option<&'a GoldenClickReward> {
+        let total: f64 = rewards.iter().map(|reward| reward.weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut roll = self.rng.next_f64() * total;
+        for reward in rewards {
+            if roll < reward.weight {
+                return Some(&reward.reward);
+            }
+            roll -= reward.weight;
+        }
+        rewards.last().map(|reward| &reward.reward)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upgrades::{load_upgrades_config, GoldenClickConfig};
+
+    fn seeded(seed: u64, upgrades_config: &UpgradesConfig) -> GameState {
+        GameState::new(upgrades_config, seed)
+    }
+
+    /// The config with a golden click that always spawns and always pays
+    /// out `reward`.
+    fn certain_golden_click(reward: GoldenClickReward) -> UpgradesConfig {
+        let mut config = load_upgrades_config();
+        config.random_events.golden_click = GoldenClickConfig {
+            spawn_chance_per_second: 1.0,
+            lifetime: 10.0,
+            rewards: vec![WeightedReward {
+                weight: 1.0,
+                reward,
+            }],
+        };
+        config
+    }
+
+    #[test]
+    fn crits_at_the_configured_rate() {
+        let config = load_upgrades_config();
+        let chance = config.random_events.critical_click.chance;
+        let rolls = |seed| {
+            let mut state = seeded(seed, &config);
+            (0..10_000)
+                .map(|_| state.roll_critical(&config).is_some())
+                .collect::<Vec<_>>()
+        };
+        let crits = rolls(7);
+        let count = crits.iter().filter(|crit| **crit).count() as f64;
+        assert!((count / 10_000.0 - chance).abs() < 0.01, "{} crits", count);
+        assert_eq!(rolls(7), crits);
+    }
+
+    #[test]
+    fn golden_clicks_spawn_and_replay_from_the_same_seed() {
+        let config = load_upgrades_config();
+        let spawns = |seed| {
+            let mut state = seeded(seed, &config);
+            (0..2_000)
+                .filter_map(|_| {
+                    state.statistics.time_played += 1.0;
+                    state.advance_golden_click(1.0, &config)
+                })
+                .collect::<Vec<_>>()
+        };
+        let updates = spawns(7);
+        assert!(updates.contains(&GoldenClickUpdate::Spawned));
+        assert_eq!(spawns(7), updates);
+    }
+
+    #[test]
+    fn uncollected_golden_clicks_are_missed() {
+        let config = certain_golden_click(GoldenClickReward::ProductionSeconds { seconds: 60.0 });
+        let mut state = seeded(7, &config);
+        assert_eq!(
+            state.advance_golden_click(1.0, &config),
+            Some(GoldenClickUpdate::Spawned)
+        );
+        let golden_click = state.golden_click.clone().unwrap();
+        assert_eq!(golden_click.expires_at, 10.0);
+        assert!((0.0..1.0).contains(&golden_click.x));
+        assert!((0.0..1.0).contains(&golden_click.y));
+
+        state.statistics.time_played = 5.0;
+        assert_eq!(state.advance_golden_click(1.0, &config), None);
+        state.statistics.time_played = 10.0;
+        assert_eq!(
+            state.advance_golden_click(1.0, &config),
+            Some(GoldenClickUpdate::Missed)
+        );
+        assert!(state.golden_click.is_none());
+    }
+
+    #[test]
+    fn collecting_grants_the_reward_once() {
+        let reward = GoldenClickReward::Resource {
+            resource: "gems".to_string(),
+            amount: BigNum::from(3),
+        };
+        let config = certain_golden_click(reward.clone());
+        let mut state = seeded(7, &config);
+        assert_eq!(state.collect_golden_click(&config), None);
+
+        state.advance_golden_click(1.0, &config);
+        assert_eq!(state.collect_golden_click(&config), Some(reward));
+        assert_eq!(state.resources.get("gems"), BigNum::from(3));
+        assert!(state.golden_click.is_none());
+        assert_eq!(state.collect_golden_click(&config), None);
+    }
+}
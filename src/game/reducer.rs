@@ -0,0 +1,338 @@
+use crate::game::{
+    BigNum, BuyAmount, ChallengeUpdate, GameAction, GameEvent, GameParameter, GameState,
+    GoldenClickUpdate,
+};
+use crate::upgrades::UpgradesConfig;
+
+/// Applies `action` to `state`, returning the next state and what happened.
+///
+/// This is the only place game actions are interpreted; it has no side
+/// effects, so storage and UI concerns stay with the caller.
+pub fn reduce(
+    state: &GameState,
+    action: &GameAction,
+    upgrades_config: &UpgradesConfig,
+) -> (GameState, Vec<GameEvent>) {
+    let mut new_state = state.clone();
+    let mut events = Vec::new();
+
+    match action {
+        GameAction::Click => {
+            let (value, critical) = new_state.click(upgrades_config);
+            events.push(GameEvent::Clicked { value, critical });
+        }
+        GameAction::Reset(difficulty) => {
+            new_state.reset(difficulty.clone(), upgrades_config);
+            events.push(GameEvent::Reset);
+        }
+        GameAction::Prestige => {
+            // The prestige would apply to the challenge run and be lost with it.
+            if new_state.challenges.active.is_none()
+                && new_state.prestige_gain(upgrades_config) > BigNum::ZERO
+            {
+                let gained = new_state.prestige(upgrades_config);
+                events.push(GameEvent::Prestiged { gained });
+            } else {
+                events.push(GameEvent::PrestigeUnavailable);
+            }
+        }
+        GameAction::BuyUpgrade(upgrade, amount) => {
+            events.push(buy_upgrade(
+                &mut new_state,
+                upgrade,
+                *amount,
+                upgrades_config,
+            ));
+        }
+        GameAction::SetAutobuyRule(upgrade, rule) => match upgrades_config.get(upgrade) {
+            Some(definition) => {
+                new_state.set_autobuy_rule(upgrade, rule.clone());
+                events.push(GameEvent::AutobuyRuleChanged {
+                    upgrade: definition.name.clone(),
+                    rule: rule.clone(),
+                });
+            }
+            None => events.push(GameEvent::UnknownUpgrade {
+                upgrade: upgrade.clone(),
+            }),
+        },
+        GameAction::UpdateGameParameter(param) => {
+            match param {
+                GameParameter::BaseMultiplier(value) => {
+                    new_state.base_multiplier = *value;
+                }
+                GameParameter::CostScaling(value) => {
+                    new_state.cost_scaling = *value;
+                }
+                GameParameter::AutoClickerEfficiency(value) => {
+                    new_state.auto_clicker_efficiency = *value;
+                }
+            }
+            events.push(GameEvent::ParameterUpdated(param.clone()));
+        }
+        GameAction::ActivateBuff(buff) => match upgrades_config.buff(buff) {
+            Some(definition) => {
+                new_state.activate_buff(definition);
+                events.push(GameEvent::BuffActivated {
+                    name: definition.name.clone(),
+                });
+            }
+            None => events.push(GameEvent::UnknownBuff { buff: buff.clone() }),
+        },
+        GameAction::Tick(seconds) => {
+            for buff in new_state.tick(*seconds, upgrades_config) {
+                let name = upgrades_config
+                    .buff(&buff.id)
+                    .map_or(buff.id, |definition| definition.name.clone());
+                events.push(GameEvent::BuffExpired { name });
+            }
+            match new_state.advance_golden_click(*seconds, upgrades_config) {
+                Some(GoldenClickUpdate::Spawned) => events.push(GameEvent::GoldenClickSpawned),
+                Some(GoldenClickUpdate::Missed) => events.push(GameEvent::GoldenClickMissed),
+                None => {}
+            }
+            for purchase in new_state.run_autobuyer(*seconds, upgrades_config) {
+                events.push(GameEvent::AutoPurchased {
+                    upgrade: purchase.upgrade,
+                    resource: purchase.resource,
+                    cost: purchase.cost,
+                });
+            }
+        }
+        GameAction::StartChallenge(challenge) => match upgrades_config.challenge(challenge) {
+            Some(_) if new_state.challenges.active.is_some() => {
+                events.push(GameEvent::ChallengeInProgress);
+            }
+            Some(definition) => {
+                new_state.start_challenge(definition, upgrades_config);
+                events.push(GameEvent::ChallengeStarted {
+                    name: definition.name.clone(),
+                });
+            }
+            None => events.push(GameEvent::UnknownChallenge {
+                challenge: challenge.clone(),
+            }),
+        },
+        GameAction::AbandonChallenge => {
+            if let Some(id) = new_state.end_challenge(false, upgrades_config) {
+                events.push(GameEvent::ChallengeAbandoned {
+                    name: challenge_name(&id, upgrades_config),
+                });
+            }
+        }
+        GameAction::CollectGoldenClick => {
+            if let Some(reward) = new_state.collect_golden_click(upgrades_config) {
+                events.push(GameEvent::GoldenClickCollected { reward });
+            }
+        }
+        GameAction::Load(loaded_state) => {
+            new_state = (**loaded_state).clone();
+            events.push(GameEvent::Loaded);
+        }
+        GameAction::CheckUnlocks => {}
+    }
+
+    for name in new_state.unlock_achievements(upgrades_config) {
+        events.push(GameEvent::AchievementUnlocked { name });
+    }
+    match new_state.check_challenge(upgrades_config) {
+        Some(ChallengeUpdate::Completed(id)) => events.push(GameEvent::ChallengeCompleted {
+            name: challenge_name(&id, upgrades_config),
+            bonus: upgrades_config
+                .challenge(&id)
+                .map_or(1.0, |definition| definition.bonus),
+        }),
+        Some(ChallengeUpdate::Failed(id)) => events.push(GameEvent::ChallengeFailed {
+            name: challenge_name(&id, upgrades_config),
+        }),
+        None => {}
+    }
+    // After achievements, since upgrades can require them.
+    for name in new_state.unlock_upgrades(upgrades_config) {
+        events.push(GameEvent::UpgradeUnlocked { name });
+    }
+
+    (new_state, events)
+}
+
+fn challenge_name(id: &str, upgrades_config: &UpgradesConfig) -> String {
+    upgrades_config
+        .challenge(id)
+        .map_or_else(|| id.to_string(), |definition| definition.name.clone())
+}
+
+fn buy_upgrade(
+    state: &mut GameState,
+    upgrade: &str,
+    amount: BuyAmount,
+    upgrades_config: &UpgradesConfig,
+) -> GameEvent {
+    let Some(definition) = upgrades_config.get(upgrade) else {
+        return GameEvent::UnknownUpgrade {
+            upgrade: upgrade.to_string(),
+        };
+    };
+    if let Some(reason) = state.locked_reason(definition, upgrades_config) {
+        return GameEvent::UpgradeLocked {
+            upgrade: upgrade.to_string(),
+            reason,
+        };
+    }
+    if state.levels_left(definition) == 0 {
+        return GameEvent::UpgradeMaxed {
+            upgrade: upgrade.to_string(),
+        };
+    }
+    let levels = match amount {
+        BuyAmount::Levels(levels) => levels.max(1),
+        BuyAmount::Max => state
+            .max_affordable_levels(definition, upgrades_config)
+            .max(1),
+    }
+    .min(state.max_bulk_levels(definition));
+    let resource = upgrades_config.cost_resource(definition).to_string();
+    let cost = state.bulk_upgrade_cost(definition, levels);
+    if state.resources.get(&resource) < cost {
+        return GameEvent::InsufficientFunds {
+            upgrade: upgrade.to_string(),
+            resource,
+            cost,
+        };
+    }
+    state.purchase(upgrade, levels, &resource, cost);
+    GameEvent::Purchased {
+        upgrade: upgrade.to_string(),
+        levels,
+        resource,
+        cost,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Difficulty, MAX_UPGRADE_LEVEL};
+    use crate::upgrades::load_upgrades_config;
+
+    fn with_gold(amount: i32, upgrades_config: &UpgradesConfig) -> GameState {
+        let mut state = GameState::new(upgrades_config, 0);
+        state.resources.add("gold", BigNum::from(amount));
+        state
+    }
+
+    fn buy(upgrade: &str) -> GameAction {
+        GameAction::BuyUpgrade(upgrade.to_string(), BuyAmount::Levels(1))
+    }
+
+    #[test]
+    fn click_earns_its_value() {
+        let config = load_upgrades_config();
+        let state = GameState::new(&config, 0);
+        let (state, events) = reduce(&state, &GameAction::Click, &config);
+        match &events[0] {
+            GameEvent::Clicked { value, .. } => {
+                assert!(*value > BigNum::ZERO);
+                assert_eq!(state.resources.get("gold"), *value);
+            }
+            other => panic!("expected a click, got {:?}", other),
+        }
+        assert_eq!(state.statistics.clicks, 1);
+    }
+
+    #[test]
+    fn buying_without_funds_changes_nothing() {
+        let config = load_upgrades_config();
+        let state = GameState::new(&config, 0);
+        let (new_state, events) = reduce(&state, &buy("auto_clicker"), &config);
+        assert!(matches!(
+            &events[0],
+            GameEvent::InsufficientFunds { upgrade, resource, .. }
+                if upgrade == "auto_clicker" && resource == "gold"
+        ));
+        assert_eq!(new_state, state);
+    }
+
+    #[test]
+    fn buying_pays_for_a_level() {
+        let config = load_upgrades_config();
+        let state = with_gold(100, &config);
+        let cost = state.upgrade_cost(config.get("auto_clicker").unwrap());
+        let (new_state, events) = reduce(&state, &buy("auto_clicker"), &config);
+        assert_eq!(
+            events[0],
+            GameEvent::Purchased {
+                upgrade: "auto_clicker".to_string(),
+                levels: 1,
+                resource: "gold".to_string(),
+                cost,
+            }
+        );
+        assert_eq!(new_state.resources.get("gold"), BigNum::from(100) - cost);
+        assert_eq!(new_state.upgrades.level("auto_clicker"), 1);
+    }
+
+    #[test]
+    fn locked_upgrades_cannot_be_bought() {
+        let config = load_upgrades_config();
+        let state = with_gold(1_000_000, &config);
+        let (new_state, events) = reduce(&state, &buy("clicker_factory"), &config);
+        assert!(matches!(
+            &events[0],
+            GameEvent::UpgradeLocked { upgrade, .. } if upgrade == "clicker_factory"
+        ));
+        assert_eq!(new_state.upgrades.level("clicker_factory"), 0);
+    }
+
+    #[test]
+    fn maxed_upgrades_cannot_be_bought() {
+        let config = load_upgrades_config();
+        let mut state = with_gold(1_000_000, &config);
+        state.upgrades.add("auto_clicker", MAX_UPGRADE_LEVEL);
+        let action = GameAction::BuyUpgrade("auto_clicker".to_string(), BuyAmount::Max);
+        let (new_state, events) = reduce(&state, &action, &config);
+        assert_eq!(
+            events[0],
+            GameEvent::UpgradeMaxed {
+                upgrade: "auto_clicker".to_string()
+            }
+        );
+        assert_eq!(new_state.upgrades.level("auto_clicker"), MAX_UPGRADE_LEVEL);
+    }
+
+    #[test]
+    fn custom_cost_purchases_stop_at_the_summed_levels() {
+        let config = load_upgrades_config();
+        let gem_lens = config.get("gem_lens").unwrap();
+        assert!(gem_lens.cost.is_some());
+        let mut state = GameState::new(&config, 0);
+        state.upgrades.add("gem_mine", 1);
+        state.unlock_upgrades(&config);
+        state.resources.add("gems", BigNum::new(1.0, 400));
+        let cost = state.bulk_upgrade_cost(gem_lens, 1000);
+
+        let action = GameAction::BuyUpgrade("gem_lens".to_string(), BuyAmount::Levels(5000));
+        let (new_state, events) = reduce(&state, &action, &config);
+        assert_eq!(
+            events[0],
+            GameEvent::Purchased {
+                upgrade: "gem_lens".to_string(),
+                levels: 1000,
+                resource: "gems".to_string(),
+                cost,
+            }
+        );
+        assert_eq!(new_state.upgrades.level("gem_lens"), 1000);
+    }
+
+    #[test]
+    fn reset_starts_over() {
+        let config = load_upgrades_config();
+        let mut state = with_gold(500, &config);
+        state.upgrades.add("auto_clicker", 3);
+        let (new_state, events) =
+            reduce(&state, &GameAction::Reset(Difficulty::default()), &config);
+        assert_eq!(events, vec![GameEvent::Reset]);
+        assert!(new_state.resources.is_empty());
+        assert_eq!(new_state.upgrades.level("auto_clicker"), 0);
+    }
+}
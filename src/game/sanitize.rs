@@ -0,0 +1,292 @@
+use crate::game::{BigNum, GameState, Generated, Resources, Upgrades};
+use crate::upgrades::{ChallengeModifier, UpgradesConfig};
+use std::fmt;
+
+/// Highest upgrade level a loaded state may hold; anything above is clamped.
+pub const MAX_UPGRADE_LEVEL: i32 = 1_000_000;
+
+/// Something wrong with a loaded state. Out-of-range values have an obvious
+/// nearest valid value and are fixed; values that are not numbers at all
+/// leave nothing to go on, so they are fatal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveIssue {
+    /// An amount that cannot be negative was; it is now zero.
+    NegativeAmount { field: String, amount: BigNum },
+    /// An amount is infinite or NaN.
+    NotFinite { field: String },
+    /// A multiplier was not a positive number, or another parameter was out
+    /// of range; it is back to its default.
+    InvalidParameter {
+        field: String,
+        value: f64,
+        default: f64,
+    },
+    /// An upgrade level was outside `0..=MAX_UPGRADE_LEVEL`; it is clamped.
+    UpgradeLevelOutOfRange { upgrade: String, level: i32 },
+    /// Levels or generated amounts of an upgrade the config does not have
+    /// were dropped.
+    UnknownUpgrade { upgrade: String },
+    /// An amount of a resource the config does not have was dropped.
+    UnknownResource { resource: String },
+    /// A time was negative or not a number; it is now zero.
+    InvalidTime { field: String, value: f64 },
+    /// A buff whose multiplier or duration was not a positive number was
+    /// dropped.
+    InvalidBuff { buff: String },
+    /// A multiplier of the active challenge was not a positive number; it
+    /// was dropped.
+    InvalidChallengeModifier { challenge: String, value: f64 },
+}
+
+/// Whether `value` can be used as a game parameter or difficulty multiplier.
+pub fn is_valid_parameter(value: f64) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+impl SaveIssue {
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, SaveIssue::NotFinite { .. })
+    }
+}
+
+impl fmt::Display for SaveIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveIssue::NegativeAmount { field, amount } => {
+                write!(f, "{} was negative ({}), set to 0", field, amount)
+            }
+            SaveIssue::NotFinite { field } => write!(f, "{} is not a finite number", field),
+            SaveIssue::InvalidParameter {
+                field,
+                value,
+                default,
+            } => write!(f, "{} was {}, reset to {}", field, value, default),
+            SaveIssue::UpgradeLevelOutOfRange { upgrade, level } => write!(
+                f,
+                "{} level {} was out of range, clamped to 0..={}",
+                upgrade, level, MAX_UPGRADE_LEVEL
+            ),
+            SaveIssue::UnknownUpgrade { upgrade } => {
+                write!(f, "unknown upgrade {} was dropped", upgrade)
+            }
+            SaveIssue::UnknownResource { resource } => {
+                write!(f, "unknown resource {} was dropped", resource)
+            }
+            SaveIssue::InvalidTime { field, value } => {
+                write!(f, "{} was {}, set to 0", field, value)
+            }
+            SaveIssue::InvalidBuff { buff } => {
+                write!(f, "buff {} was not valid and was dropped", buff)
+            }
+            SaveIssue::InvalidChallengeModifier { challenge, value } => write!(
+                f,
+                "challenge {} had a multiplier of {}, dropped",
+                challenge, value
+            ),
+        }
+    }
+}
+
+impl GameState {
+    /// Fixes what can be fixed in a state read from outside the game and
+    /// reports everything found. The state is only fit to play if none of
+    /// the issues [`is_fatal`](SaveIssue::is_fatal).
+    pub fn sanitize(&mut self, upgrades_config: &UpgradesConfig) -> Vec<SaveIssue> {
+        let mut issues = Vec::new();
+
+        let mut resources = Resources::default();
+        for (resource, amount) in self.resources.iter() {
+            if upgrades_config.resource(resource).is_none() {
+                issues.push(SaveIssue::UnknownResource {
+                    resource: resource.clone(),
+                });
+                continue;
+            }
+            let field = format!("resources.{}", resource);
+            resources.add(resource, sanitize_amount(field, *amount, &mut issues));
+        }
+        self.resources = resources;
+
+        let mut upgrades = Upgrades::default();
+        for (upgrade, level) in self.upgrades.iter() {
+            if upgrades_config.get(upgrade).is_none() {
+                issues.push(SaveIssue::UnknownUpgrade {
+                    upgrade: upgrade.clone(),
+                });
+                continue;
+            }
+            if !(0..=MAX_UPGRADE_LEVEL).contains(level) {
+                issues.push(SaveIssue::UpgradeLevelOutOfRange {
+                    upgrade: upgrade.clone(),
+                    level: *level,
+                });
+            }
+            upgrades.add(upgrade, (*level).clamp(0, MAX_UPGRADE_LEVEL));
+        }
+        self.upgrades = upgrades;
+
+        let mut generated = Generated::default();
+        for (upgrade, amount) in self.generated.iter() {
+            if upgrades_config.get(upgrade).is_none() {
+                issues.push(SaveIssue::UnknownUpgrade {
+                    upgrade: upgrade.clone(),
+                });
+                continue;
+            }
+            let field = format!("generated.{}", upgrade);
+            generated.add(upgrade, sanitize_amount(field, *amount, &mut issues));
+        }
+        self.generated = generated;
+
+        self.prestige.points = sanitize_amount(
+            "prestige.points".to_string(),
+            self.prestige.points,
+            &mut issues,
+        );
+        self.prestige.lifetime_earned = sanitize_amount(
+            "prestige.lifetime_earned".to_string(),
+            self.prestige.lifetime_earned,
+            &mut issues,
+        );
+
+        let defaults = &upgrades_config.defaults;
+        let multipliers = &mut self.difficulty.multipliers;
+        let parameters = [
+            (
+                "base_multiplier",
+                &mut self.base_multiplier,
+                defaults.base_multiplier,
+            ),
+            (
+                "cost_scaling",
+                &mut self.cost_scaling,
+                defaults.cost_scaling,
+            ),
+            (
+                "auto_clicker_efficiency",
+                &mut self.auto_clicker_efficiency,
+                defaults.auto_clicker_efficiency,
+            ),
+            (
+                "difficulty.base_multiplier",
+                &mut multipliers.base_multiplier,
+                1.0,
+            ),
+            (
+                "difficulty.cost_scaling",
+                &mut multipliers.cost_scaling,
+                1.0,
+            ),
+            (
+                "difficulty.auto_clicker_efficiency",
+                &mut multipliers.auto_clicker_efficiency,
+                1.0,
+            ),
+            ("difficulty.base_cost", &mut multipliers.base_cost, 1.0),
+            (
+                "difficulty.offline_efficiency",
+                &mut multipliers.offline_efficiency,
+                1.0,
+            ),
+        ];
+        for (field, value, default) in parameters {
+            if !is_valid_parameter(*value) {
+                issues.push(SaveIssue::InvalidParameter {
+                    field: field.to_string(),
+                    value: *value,
+                    default,
+                });
+                *value = default;
+            }
+        }
+
+        if !self.automation.charge.is_finite() || self.automation.charge < 0.0 {
+            issues.push(SaveIssue::InvalidParameter {
+                field: "automation.charge".to_string(),
+                value: self.automation.charge,
+                default: 0.0,
+            });
+            self.automation.charge = 0.0;
+        }
+
+        self.buffs.retain(|buff| {
+            let valid = is_valid_parameter(buff.multiplier)
+                && is_valid_parameter(buff.duration)
+                && buff.started_at.is_finite();
+            if !valid {
+                issues.push(SaveIssue::InvalidBuff {
+                    buff: buff.id.clone(),
+                });
+            }
+            valid
+        });
+
+        if let Some(challenge) = &mut self.challenges.active {
+            challenge.modifiers.retain(|modifier| match modifier {
+                ChallengeModifier::CostMultiplier { multiplier }
+                | ChallengeModifier::ProductionMultiplier { multiplier }
+                    if !is_valid_parameter(*multiplier) =>
+                {
+                    issues.push(SaveIssue::InvalidChallengeModifier {
+                        challenge: challenge.id.clone(),
+                        value: *multiplier,
+                    });
+                    false
+                }
+                _ => true,
+            });
+        }
+
+        let statistics = &mut self.statistics;
+        statistics.earned = sanitize_amounts("statistics.earned", &statistics.earned, &mut issues);
+        statistics.spent = sanitize_amounts("statistics.spent", &statistics.spent, &mut issues);
+        statistics.best_clicks_per_second = sanitize_amount(
+            "statistics.best_clicks_per_second".to_string(),
+            statistics.best_clicks_per_second,
+            &mut issues,
+        );
+        let times = [
+            ("last_saved", &mut self.last_saved),
+            ("statistics.time_played", &mut statistics.time_played),
+            (
+                "statistics.longest_session",
+                &mut statistics.longest_session,
+            ),
+        ];
+        for (field, value) in times {
+            if !value.is_finite() || *value < 0.0 {
+                issues.push(SaveIssue::InvalidTime {
+                    field: field.to_string(),
+                    value: *value,
+                });
+                *value = 0.0;
+            }
+        }
+
+        if let Some(stashed_run) = &mut self.challenges.stashed_run {
+            issues.extend(stashed_run.sanitize(upgrades_config));
+        }
+        issues
+    }
+}
+
+fn sanitize_amounts(field: &str, amounts: &Resources, issues: &mut Vec<SaveIssue>) -> Resources {
+    let mut sanitized = Resources::default();
+    for (resource, amount) in amounts.iter() {
+        let field = format!("{}.{}", field, resource);
+        sanitized.add(resource, sanitize_amount(field, *amount, issues));
+    }
+    sanitized
+}
+
+fn sanitize_amount(field: String, amount: BigNum, issues: &mut Vec<SaveIssue>) -> BigNum {
+    if !amount.is_finite() {
+        issues.push(SaveIssue::NotFinite { field });
+        amount
+    } else if amount.is_negative() {
+        issues.push(SaveIssue::NegativeAmount { field, amount });
+        BigNum::ZERO
+    } else {
+        amount
+    }
+}
@@ -1,13 +1,45 @@
-use crate::upgrades::load_upgrades_config;
+use crate::game::{
+    Achievements, ActiveBuff, Automation, BigNum, Challenges, Difficulty, Generated, GoldenClick,
+    PrestigeState, Rng, Statistics, UnlockedUpgrades, MAX_UPGRADE_LEVEL,
+};
+use crate::upgrades::{BuffTarget, Formula, UpgradeDefinition, UpgradeEffect, UpgradesConfig};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, Default, PartialEq)]
+/// Projections further out than this (about 30,000 years) count as never.
+const MAX_PROJECTION_SECONDS: f64 = 1e12;
+/// Halvings of the search window in `time_to_reach_resources`.
+const BISECTION_STEPS: u32 = 40;
+/// Most levels a bulk purchase sums one by one for a custom cost formula.
+const MAX_SUMMED_LEVELS: i32 = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct GameState {
-    pub counter: i32,
-    pub clicks_per_second: i32,
+    pub resources: Resources,
     pub last_saved: f64,
     pub upgrades: Upgrades,
-    pub x2_upgrade_cost: i32,
+    #[serde(default)]
+    pub generated: Generated,
+    #[serde(default)]
+    pub unlocked_upgrades: UnlockedUpgrades,
+    #[serde(default)]
+    pub prestige: PrestigeState,
+    #[serde(default)]
+    pub achievements: Achievements,
+    #[serde(default)]
+    pub statistics: Statistics,
+    #[serde(default)]
+    pub buffs: Vec<ActiveBuff>,
+    #[serde(default)]
+    pub golden_click: Option<GoldenClick>,
+    #[serde(default)]
+    pub rng: Rng,
+    #[serde(default)]
+    pub automation: Automation,
+    #[serde(default)]
+    pub challenges: Challenges,
+    #[serde(default)]
+    pub difficulty: Difficulty,
 
     // Developer panel parameters
     pub base_multiplier: f64,
@@ -15,114 +47,382 @@ pub struct GameState {
     pub auto_clicker_efficiency: f64,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, Default, PartialEq)]
-pub struct Upgrades {
-    pub auto_clicker: i32,
-    pub click_multiplier: i32,
+/// Owned upgrade levels, keyed by upgrade id.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Upgrades(BTreeMap<String, i32>);
+
+/// Amounts held of each resource, keyed by resource id.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Resources(BTreeMap<String, BigNum>);
+
+impl Resources {
+    pub fn get(&self, id: &str) -> BigNum {
+        self.0.get(id).copied().unwrap_or_default()
+    }
+
+    pub fn add(&mut self, id: &str, amount: BigNum) {
+        *self.0.entry(id.to_string()).or_default() += amount;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &BigNum)> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Upgrades {
+    pub fn level(&self, id: &str) -> i32 {
+        self.0.get(id).copied().unwrap_or(0)
+    }
+
+    pub fn add(&mut self, id: &str, levels: i32) {
+        let level = self.0.entry(id.to_string()).or_insert(0);
+        *level = level.saturating_add(levels);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &i32)> {
+        self.0.iter()
+    }
 }
 
 impl GameState {
-    pub fn new() -> Self {
-        let upgrades_config = load_upgrades_config();
+    /// A fresh game whose random events are drawn from `seed`. `last_saved`
+    /// stays unset until the game is first saved.
+    pub fn new(upgrades_config: &UpgradesConfig, seed: u64) -> Self {
         Self {
-            counter: 0,
-            clicks_per_second: 0,
-            last_saved: js_sys::Date::now(),
+            resources: Resources::default(),
+            last_saved: 0.0,
             upgrades: Upgrades::default(),
-            base_multiplier: upgrades_config.click_multiplier.multiplier.unwrap_or(1.0),
-            cost_scaling: upgrades_config.click_multiplier.cost_scaling,
-            auto_clicker_efficiency: upgrades_config.auto_clicker.efficiency.unwrap_or(1.0),
-            x2_upgrade_cost: upgrades_config.click_multiplier.base_cost,
+            generated: Generated::default(),
+            unlocked_upgrades: UnlockedUpgrades::default(),
+            prestige: PrestigeState::default(),
+            achievements: Achievements::default(),
+            statistics: Statistics::default(),
+            buffs: Vec::new(),
+            golden_click: None,
+            rng: Rng::new(seed),
+            automation: Automation::default(),
+            challenges: Challenges::default(),
+            difficulty: Difficulty::default(),
+            base_multiplier: upgrades_config.defaults.base_multiplier,
+            cost_scaling: upgrades_config.defaults.cost_scaling,
+            auto_clicker_efficiency: upgrades_config.defaults.auto_clicker_efficiency,
         }
     }
 
-    pub fn increment_counter(&mut self) {
-        self.counter += self.calculate_click_value();
+    /// Earns one manual click, returning its value and whether it was a
+    /// critical hit.
+    pub fn click(&mut self, upgrades_config: &UpgradesConfig) -> (BigNum, bool) {
+        let mut value = self.calculate_click_value(upgrades_config);
+        let critical = self.roll_critical(upgrades_config);
+        if let Some(multiplier) = critical {
+            value = value * multiplier;
+        }
+        self.statistics.clicks += 1;
+        self.earn(upgrades_config.primary_resource(), value, upgrades_config);
+        (value, critical.is_some())
     }
 
-    pub fn reset(&mut self) {
-        *self = Self::new();
-        self.last_saved = js_sys::Date::now();
-        self.counter = 0;
-        self.clicks_per_second = 0;
-        self.upgrades = Upgrades::default();
+    /// Adds freshly produced resources; the primary resource also counts
+    /// toward lifetime earnings.
+    pub fn earn(&mut self, resource: &str, amount: BigNum, upgrades_config: &UpgradesConfig) {
+        self.resources.add(resource, amount);
+        self.statistics.earned.add(resource, amount);
+        if resource == upgrades_config.primary_resource() {
+            self.prestige.lifetime_earned += amount;
+        }
     }
 
-    pub fn calculate_clicks_per_second(&self) -> f64 {
-        if self.upgrades.auto_clicker > 0 {
-            let base_cps = self.upgrades.auto_clicker as f64;
-            let multiplier = (1.0 + self.upgrades.click_multiplier as f64) * self.base_multiplier;
-            base_cps * multiplier * self.auto_clicker_efficiency
-        } else {
-            0.0
+    /// Pays `cost` of `resource` for `levels` more levels of `upgrade`.
+    pub fn purchase(&mut self, upgrade: &str, levels: i32, resource: &str, cost: BigNum) {
+        self.resources.add(resource, -cost);
+        self.upgrades.add(upgrade, levels);
+        self.statistics.spent.add(resource, cost);
+        self.statistics.upgrades_bought.add(upgrade, levels);
+    }
+
+    /// Wipes all progress and starts a new game on `difficulty`. The new
+    /// game is seeded from the old one's random stream, so a reset does not
+    /// replay the same events.
+    pub fn reset(&mut self, difficulty: Difficulty, upgrades_config: &UpgradesConfig) {
+        let seed = self.rng.next_u64();
+        *self = Self::new(upgrades_config, seed);
+        self.difficulty = difficulty;
+    }
+
+    /// Advances the simulation by `seconds` of production, returning the
+    /// buffs that ran out along the way.
+    pub fn tick(&mut self, seconds: f64, upgrades_config: &UpgradesConfig) -> Vec<ActiveBuff> {
+        let mut expired = self.expire_buffs();
+        let mut remaining = seconds;
+        while remaining > 0.0 {
+            // Split the step where a buff runs out so it boosts exactly its duration.
+            let step = self
+                .next_buff_expiry()
+                .map_or(remaining, |expiry| expiry.min(remaining));
+            let production = self.production_over(step, upgrades_config);
+            self.collect(&production, upgrades_config);
+            self.statistics.time_played += step;
+            self.statistics.session_time += step;
+            remaining -= step;
+            expired.extend(self.expire_buffs());
         }
+        let clicks_per_second = self.calculate_clicks_per_second(upgrades_config);
+        let statistics = &mut self.statistics;
+        statistics.longest_session = statistics.longest_session.max(statistics.session_time);
+        statistics.best_clicks_per_second =
+            statistics.best_clicks_per_second.max(clicks_per_second);
+        expired
     }
 
-    pub fn calculate_click_value(&self) -> i32 {
-        let multiplier = (1.0 + self.upgrades.click_multiplier as f64) * self.base_multiplier;
-        multiplier.round() as i32
+    /// Sum of every click multiplier upgrade's contribution, on top of the base 1.
+    pub fn click_multiplier(&self, upgrades_config: &UpgradesConfig) -> f64 {
+        1.0 + upgrades_config
+            .upgrades
+            .iter()
+            .map(|upgrade| match upgrade.effect {
+                UpgradeEffect::ClickMultiplier { .. } => {
+                    self.upgrades.level(&upgrade.id) as f64
+                        * self.effect_amount(upgrade)
+                        * self.milestone_multiplier(upgrade)
+                }
+                _ => 0.0,
+            })
+            .sum::<f64>()
     }
 
-    pub fn calculate_upgrade_cost(&self, current_level: i32) -> i32 {
-        let upgrades_config = load_upgrades_config();
-        let base_cost = upgrades_config.click_multiplier.base_cost;
-        (base_cost as f64 * self.cost_scaling.powi(current_level)).round() as i32
+    /// Units of `resource` produced per second right now by the auto clickers
+    /// at the bottom of each generator chain.
+    pub fn production_per_second(
+        &self,
+        resource: &str,
+        upgrades_config: &UpgradesConfig,
+    ) -> BigNum {
+        upgrades_config
+            .upgrades
+            .iter()
+            .filter(|upgrade| upgrades_config.produced_resource(&upgrade.effect) == Some(resource))
+            .map(|upgrade| self.owned(&upgrade.id) * self.unit_production(upgrade, upgrades_config))
+            .sum()
     }
 
-    pub fn time_to_next_upgrade(&self) -> f64 {
-        let next_upgrade_cost = self.calculate_upgrade_cost(self.upgrades.click_multiplier);
-        let current_production = self.calculate_clicks_per_second();
-        if current_production <= 0.0 {
-            f64::INFINITY
-        } else {
-            (next_upgrade_cost as f64 - self.counter as f64) / current_production
+    /// Production per second of the primary resource.
+    pub fn calculate_clicks_per_second(&self, upgrades_config: &UpgradesConfig) -> BigNum {
+        self.production_per_second(upgrades_config.primary_resource(), upgrades_config)
+    }
+
+    pub fn calculate_click_value(&self, upgrades_config: &UpgradesConfig) -> BigNum {
+        let multiplier = self.click_multiplier(upgrades_config)
+            * self.effective_base_multiplier()
+            * self.buff_multiplier(BuffTarget::ClickValue);
+        (BigNum::from(multiplier) * self.bonus_multiplier(upgrades_config)).round()
+    }
+
+    /// Bonuses applied on top of upgrade effects to both clicks and production.
+    pub fn bonus_multiplier(&self, upgrades_config: &UpgradesConfig) -> BigNum {
+        self.prestige_multiplier(upgrades_config)
+            * self.achievement_multiplier(upgrades_config)
+            * self.challenge_multiplier(upgrades_config)
+    }
+
+    pub fn time_to_next_upgrade(&self, upgrades_config: &UpgradesConfig) -> f64 {
+        upgrades_config
+            .upgrades
+            .iter()
+            .filter(|upgrade| self.is_unlocked(upgrade) && !self.disabled_by_challenge(&upgrade.id))
+            .map(|upgrade| {
+                self.time_to_reach_resources(
+                    upgrades_config.cost_resource(upgrade),
+                    self.upgrade_cost(upgrade),
+                    upgrades_config,
+                )
+            })
+            .reduce(f64::min)
+            .unwrap_or(0.0)
+    }
+
+    pub fn upgrade_cost(&self, upgrade: &UpgradeDefinition) -> BigNum {
+        self.next_level_cost(upgrade).round()
+    }
+
+    /// Total cost of the next `levels` levels of `upgrade`. The default cost
+    /// formula uses the closed form of the geometric series
+    /// `first * (r^levels - 1) / (r - 1)`; custom formulas are summed level
+    /// by level.
+    pub fn bulk_upgrade_cost(&self, upgrade: &UpgradeDefinition, levels: i32) -> BigNum {
+        if levels <= 0 {
+            return BigNum::ZERO;
+        }
+        if upgrade.cost.is_some() {
+            let total: BigNum = (0..levels.min(MAX_SUMMED_LEVELS))
+                .map(|levels_ahead| self.level_cost(upgrade, levels_ahead))
+                .sum();
+            return total.round();
         }
+        let cost_scaling = self.cost_scaling_for(upgrade);
+        let first = self.next_level_cost(upgrade);
+        let total = if cost_scaling == 1.0 {
+            first * levels as f64
+        } else {
+            first * (BigNum::from(cost_scaling).powf(levels as f64) - BigNum::ONE)
+                / (cost_scaling - 1.0)
+        };
+        total.round()
+    }
+
+    /// Levels of `upgrade` that can still be bought before it reaches
+    /// [`MAX_UPGRADE_LEVEL`].
+    pub fn levels_left(&self, upgrade: &UpgradeDefinition) -> i32 {
+        (MAX_UPGRADE_LEVEL - self.upgrades.level(&upgrade.id)).max(0)
     }
 
-    pub fn get_upgrade_cost(&self, upgrade_name: &str) -> i32 {
-        let upgrades_config = load_upgrades_config();
-        match upgrade_name {
-            "auto_clicker" => (upgrades_config.auto_clicker.base_cost as f64
-                * upgrades_config
-                    .auto_clicker
-                    .cost_scaling
-                    .powi(self.upgrades.auto_clicker))
-            .round() as i32,
-            "click_multiplier" => (upgrades_config.click_multiplier.base_cost as f64
-                * upgrades_config
-                    .click_multiplier
-                    .cost_scaling
-                    .powi(self.upgrades.click_multiplier))
-            .round() as i32,
-            _ => 0,
+    /// Most levels of `upgrade` one purchase may buy: those left below the
+    /// level cap, and for a custom cost formula no more than get summed.
+    pub fn max_bulk_levels(&self, upgrade: &UpgradeDefinition) -> i32 {
+        let levels_left = self.levels_left(upgrade);
+        if upgrade.cost.is_some() {
+            levels_left.min(MAX_SUMMED_LEVELS)
+        } else {
+            levels_left
         }
     }
 
-    pub fn apply_upgrade(&mut self, upgrade_name: &str) {
-        match upgrade_name {
-            "auto_clicker" => {
-                self.upgrades.auto_clicker += 1;
-                // Don't need to set clicks_per_second directly anymore
-            }
-            "click_multiplier" => {
-                self.upgrades.click_multiplier += 1;
+    /// Most levels of `upgrade` its cost resource can pay for at once, up
+    /// to [`GameState::max_bulk_levels`].
+    pub fn max_affordable_levels(
+        &self,
+        upgrade: &UpgradeDefinition,
+        upgrades_config: &UpgradesConfig,
+    ) -> i32 {
+        let funds = self.resources.get(upgrades_config.cost_resource(upgrade));
+        let first = self.next_level_cost(upgrade);
+        let max_levels = self.max_bulk_levels(upgrade);
+        if max_levels == 0 || first <= BigNum::ZERO || first > funds {
+            return 0;
+        }
+        if upgrade.cost.is_some() {
+            let mut total = BigNum::ZERO;
+            let mut levels = 0;
+            while levels < max_levels {
+                total += self.level_cost(upgrade, levels);
+                if total.round() > funds {
+                    break;
+                }
+                levels += 1;
             }
-            _ => {}
+            return levels;
+        }
+        let cost_scaling = self.cost_scaling_for(upgrade);
+        // Invert the series sum: r^n = funds * (r - 1) / first + 1.
+        let levels = if cost_scaling > 1.0 {
+            let ratio = funds * (cost_scaling - 1.0) / first + BigNum::ONE;
+            (ratio.log10() / cost_scaling.log10()).floor()
+        } else {
+            (funds / first).to_f64().floor()
+        };
+        // Without growing costs, funds alone put no bound on the levels.
+        let mut levels = levels.min(max_levels as f64) as i32;
+        // Per-purchase rounding can push the exact boundary one level over.
+        while levels > 0 && self.bulk_upgrade_cost(upgrade, levels) > funds {
+            levels -= 1;
         }
+        levels
     }
 
-    pub fn time_to_reach_resources(&self, target: f64) -> f64 {
-        let cps = self.calculate_clicks_per_second();
-        if cps <= 0.0 {
-            f64::INFINITY
-        } else {
-            (target - self.counter as f64) / cps
+    pub fn cost_scaling_for(&self, upgrade: &UpgradeDefinition) -> f64 {
+        upgrade.cost_scaling.unwrap_or(self.cost_scaling) * self.difficulty.multipliers.cost_scaling
+    }
+
+    fn next_level_cost(&self, upgrade: &UpgradeDefinition) -> BigNum {
+        self.level_cost(upgrade, 0)
+    }
+
+    /// Cost of the level `levels_ahead` past the next one, before rounding.
+    fn level_cost(&self, upgrade: &UpgradeDefinition, levels_ahead: i32) -> BigNum {
+        let cost = match &upgrade.cost {
+            Some(cost) => self.evaluate_formula(cost, upgrade, levels_ahead),
+            None => {
+                let level = (self.upgrades.level(&upgrade.id) + levels_ahead) as f64;
+                self.base_cost_for(upgrade)
+                    * BigNum::from(self.cost_scaling_for(upgrade)).powf(level)
+            }
+        };
+        cost * self.buff_multiplier(BuffTarget::Cost) * self.challenge_cost_multiplier()
+    }
+
+    /// Evaluates one of `upgrade`'s formulas as if `levels_ahead` more levels
+    /// had been bought.
+    pub fn evaluate_formula(
+        &self,
+        formula: &Formula,
+        upgrade: &UpgradeDefinition,
+        levels_ahead: i32,
+    ) -> BigNum {
+        formula.evaluate(&|variable| match variable {
+            "level" => Some(BigNum::from(
+                self.upgrades.level(&upgrade.id) + levels_ahead,
+            )),
+            "owned" => Some(self.owned(&upgrade.id) + BigNum::from(levels_ahead)),
+            "base_cost" => Some(self.base_cost_for(upgrade)),
+            "cost_scaling" => Some(BigNum::from(self.cost_scaling_for(upgrade))),
+            _ => variable
+                .strip_prefix("resource.")
+                .map(|resource| self.resources.get(resource)),
+        })
+    }
+
+    /// Current value of the `amount` of `upgrade`'s effect.
+    pub fn effect_amount(&self, upgrade: &UpgradeDefinition) -> f64 {
+        self.evaluate_formula(upgrade.effect.amount(), upgrade, 0)
+            .to_f64()
+    }
+
+    /// Seconds until `resource` reaches `target`. Generator chains make the
+    /// amount grow faster than linearly, so the time is searched for: double
+    /// until the target is passed, then bisect.
+    pub fn time_to_reach_resources(
+        &self,
+        resource: &str,
+        target: BigNum,
+        upgrades_config: &UpgradesConfig,
+    ) -> f64 {
+        if self.resources.get(resource) >= target {
+            return 0.0;
         }
+        let reached =
+            |seconds: f64| self.resources_at_time(resource, seconds, upgrades_config) >= target;
+        let mut upper = 1.0;
+        while !reached(upper) {
+            upper *= 2.0;
+            if upper > MAX_PROJECTION_SECONDS {
+                return f64::INFINITY;
+            }
+        }
+        let mut lower = upper / 2.0;
+        for _ in 0..BISECTION_STEPS {
+            let middle = (lower + upper) / 2.0;
+            if reached(middle) {
+                upper = middle;
+            } else {
+                lower = middle;
+            }
+        }
+        upper
     }
 
-    pub fn resources_at_time(&self, seconds: f64) -> f64 {
-        let cps = self.calculate_clicks_per_second();
-        self.counter as f64 + (cps * seconds)
+    pub fn resources_at_time(
+        &self,
+        resource: &str,
+        seconds: f64,
+        upgrades_config: &UpgradesConfig,
+    ) -> BigNum {
+        let production = self.production_over(seconds, upgrades_config);
+        self.resources.get(resource) + production.resources.get(resource)
     }
 }
 
@@ -132,3 +432,91 @@ pub enum GameParameter {
     CostScaling(f64),
     AutoClickerEfficiency(f64),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upgrades::load_upgrades_config;
+
+    fn assert_close(actual: BigNum, expected: BigNum) {
+        let error = ((actual - expected) / expected).to_f64().abs();
+        assert!(error < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    fn with_auto_clickers(levels: i32, upgrades_config: &UpgradesConfig) -> GameState {
+        let mut state = GameState::new(upgrades_config, 0);
+        state.upgrades.add("auto_clicker", levels);
+        state
+    }
+
+    #[test]
+    fn tick_produces_for_the_elapsed_time() {
+        let config = load_upgrades_config();
+        let mut state = with_auto_clickers(2, &config);
+        let rate = state.calculate_clicks_per_second(&config);
+        assert!(rate > BigNum::ZERO);
+
+        state.tick(10.0, &config);
+        assert_close(state.resources.get("gold"), rate * 10.0);
+        assert_eq!(state.statistics.time_played, 10.0);
+        assert_close(state.prestige.lifetime_earned, rate * 10.0);
+    }
+
+    #[test]
+    fn bulk_purchases_stop_at_the_level_cap() {
+        let config = load_upgrades_config();
+        let auto_clicker = config.get("auto_clicker").unwrap();
+        let mut state = GameState::new(&config, 0);
+        // Without cost growth, a large balance buys as many levels as it covers.
+        state.cost_scaling = 1.0;
+        state.resources.add("gold", BigNum::new(1.0, 30));
+        assert_eq!(
+            state.max_affordable_levels(auto_clicker, &config),
+            MAX_UPGRADE_LEVEL
+        );
+
+        state.upgrades.add("auto_clicker", MAX_UPGRADE_LEVEL - 5);
+        assert_eq!(state.max_affordable_levels(auto_clicker, &config), 5);
+        state.upgrades.add("auto_clicker", 5);
+        assert_eq!(state.max_affordable_levels(auto_clicker, &config), 0);
+    }
+
+    #[test]
+    fn upgrade_levels_saturate() {
+        let mut upgrades = Upgrades::default();
+        upgrades.add("auto_clicker", i32::MAX);
+        upgrades.add("auto_clicker", 1);
+        assert_eq!(upgrades.level("auto_clicker"), i32::MAX);
+    }
+
+    #[test]
+    fn tick_boosts_only_while_a_buff_lasts() {
+        let config = load_upgrades_config();
+        let mut state = with_auto_clickers(2, &config);
+        let rate = state.calculate_clicks_per_second(&config);
+        let frenzy = config.buff("frenzy").unwrap();
+        state.activate_buff(frenzy);
+
+        let expired = state.tick(frenzy.duration + 10.0, &config);
+        assert_eq!(expired.len(), 1);
+        assert!(state.buffs.is_empty());
+        let boosted = frenzy.duration * frenzy.multiplier + 10.0;
+        assert_close(state.resources.get("gold"), rate * boosted);
+    }
+
+    #[test]
+    fn fresh_runs_draw_their_own_random_stream() {
+        let config = load_upgrades_config();
+        let mut state = GameState::new(&config, 7);
+        assert_ne!(state.rng, GameState::new(&config, 8).rng);
+
+        let before_reset = state.rng;
+        state.reset(Difficulty::default(), &config);
+        assert_ne!(state.rng, before_reset);
+        assert_ne!(state.rng, Rng::default());
+
+        let before_prestige = state.rng;
+        state.prestige(&config);
+        assert_ne!(state.rng, before_prestige);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::game::{BigNum, Resources, Upgrades};
+use serde::{Deserialize, Serialize};
+
+/// Counters kept across every run, including through prestige resets.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Statistics {
+    /// Manual clicks; ticks of the auto clickers do not count.
+    pub clicks: u64,
+    /// Seconds of simulated play, not counting time away.
+    pub time_played: f64,
+    /// Everything earned from clicks, production and rewards, per resource.
+    #[serde(default)]
+    pub earned: Resources,
+    /// Everything paid for upgrades, per resource.
+    #[serde(default)]
+    pub spent: Resources,
+    /// Levels bought of each upgrade, by hand or by the autobuyer.
+    #[serde(default)]
+    pub upgrades_bought: Upgrades,
+    /// Seconds played since the game was opened. Not saved, so every load
+    /// starts a new session.
+    #[serde(skip)]
+    pub session_time: f64,
+    #[serde(default)]
+    pub longest_session: f64,
+    /// Highest production per second of the primary resource seen so far.
+    #[serde(default)]
+    pub best_clicks_per_second: BigNum,
+    /// Set for good once a save of this game fails its integrity check,
+    /// i.e. was edited outside the game.
+    #[serde(default)]
+    pub modified: bool,
+    /// Set for good once this game is loaded from a save from before saves
+    /// were signed, which could have been edited without a trace.
+    #[serde(default)]
+    pub unverified: bool,
+}
@@ -0,0 +1,89 @@
+use crate::game::GameState;
+use crate::upgrades::{Prerequisite, UpgradeDefinition, UpgradesConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Ids of upgrades whose prerequisites were met this run. An unlock sticks,
+/// so spending below a resource threshold does not lock an upgrade again.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(transparent)]
+pub struct UnlockedUpgrades(BTreeSet<String>);
+
+impl UnlockedUpgrades {
+    pub fn contains(&self, id: &str) -> bool {
+        self.0.contains(id)
+    }
+}
+
+impl GameState {
+    pub fn prerequisite_met(&self, prerequisite: &Prerequisite) -> bool {
+        match prerequisite {
+            Prerequisite::UpgradeLevel { upgrade, level } => self.upgrades.level(upgrade) >= *level,
+            Prerequisite::Resource { resource, amount } => self.resources.get(resource) >= *amount,
+            Prerequisite::Achievement { achievement } => self.achievements.contains(achievement),
+        }
+    }
+
+    pub fn is_unlocked(&self, upgrade: &UpgradeDefinition) -> bool {
+        upgrade.requires.is_empty()
+            || self.unlocked_upgrades.contains(&upgrade.id)
+            // Levels owned from before the requirement existed still count.
+            || self.upgrades.level(&upgrade.id) > 0
+    }
+
+    /// Unlocks every upgrade whose prerequisites now all hold, returning the
+    /// names of the ones that were newly unlocked.
+    pub fn unlock_upgrades(&mut self, upgrades_config: &UpgradesConfig) -> Vec<String> {
+        let mut unlocked = Vec::new();
+        for upgrade in &upgrades_config.upgrades {
+            if !self.is_unlocked(upgrade)
+                && upgrade
+                    .requires
+                    .iter()
+                    .all(|prerequisite| self.prerequisite_met(prerequisite))
+            {
+                self.unlocked_upgrades.0.insert(upgrade.id.clone());
+                unlocked.push(upgrade.name.clone());
+            }
+        }
+        unlocked
+    }
+
+    /// Why `upgrade` cannot be bought yet, listing its unmet prerequisites,
+    /// or `None` if it is unlocked.
+    pub fn locked_reason(
+        &self,
+        upgrade: &UpgradeDefinition,
+        upgrades_config: &UpgradesConfig,
+    ) -> Option<String> {
+        if self.disabled_by_challenge(&upgrade.id) {
+            return Some("Disabled in this challenge".to_string());
+        }
+        if self.is_unlocked(upgrade) {
+            return None;
+        }
+        let unmet = upgrade
+            .requires
+            .iter()
+            .filter(|prerequisite| !self.prerequisite_met(prerequisite))
+            .map(|prerequisite| match prerequisite {
+                Prerequisite::UpgradeLevel { upgrade, level } => {
+                    let name = upgrades_config
+                        .get(upgrade)
+                        .map_or(upgrade.as_str(), |definition| definition.name.as_str());
+                    format!("{} level {}", name, level)
+                }
+                Prerequisite::Resource { resource, amount } => {
+                    upgrades_config.format_amount(resource, *amount)
+                }
+                Prerequisite::Achievement { achievement } => {
+                    let name = upgrades_config
+                        .achievement(achievement)
+                        .map_or(achievement.as_str(), |definition| definition.name.as_str());
+                    format!("the \"{}\" achievement", name)
+                }
+            })
+            .collect::<Vec<_>>();
+        Some(format!("Requires {}", unmet.join(", ")))
+    }
+}
@@ -1,16 +1,33 @@
-use crate::game::GameState;
+use crate::game::{GameAction, GameState};
 use crate::storage::GameStorage;
 use gloo_timers::callback::Interval;
+use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
+/// Saves the game every ten seconds through `storage`, once `loaded`.
 #[hook]
-pub fn use_auto_save(state: UseStateHandle<GameState>) {
-    use_effect(move || {
-        let state = state.clone();
+pub fn use_auto_save(
+    storage: GameStorage,
+    state: GameState,
+    loaded: bool,
+    on_action: Callback<GameAction>,
+) {
+    // The interval outlives renders, so it reads the latest state through a
+    // ref. The ref stays empty until the saved game has been loaded.
+    let latest_state = use_mut_ref(|| None::<GameState>);
+    *latest_state.borrow_mut() = loaded.then_some(state);
+
+    use_effect_with((), move |_| {
         let interval = Interval::new(10000, move || {
-            let _ = GameStorage::save(&state);
-            log::debug!("Auto-saving game state");
+            on_action.emit(GameAction::CheckUnlocks);
+            if let Some(state) = latest_state.borrow().clone() {
+                let storage = storage.clone();
+                spawn_local(async move {
+                    let _ = storage.save(&state).await;
+                });
+                log::debug!("Auto-saving game state");
+            }
         });
         || drop(interval)
     });
-}
\ No newline at end of file
+}
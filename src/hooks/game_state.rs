@@ -1,58 +1,149 @@
-use crate::game::{GameAction, GameParameter, GameState};
-use crate::storage::GameStorage;
+use crate::game::{reduce, GameAction, GameEvent, GameState, OfflineProgress};
+use crate::storage::{GameStorage, SaveProblems};
+use crate::upgrades::{load_upgrades_config, UpgradesConfig};
+use std::rc::Rc;
+use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
+/// Number of events kept around for the log panel.
+const EVENT_LOG_CAPACITY: usize = 100;
+
+/// Everything the UI reads about the running game, driven by `reduce`.
+#[derive(PartialEq)]
+pub struct GameStore {
+    pub state: GameState,
+    pub upgrades_config: Rc<UpgradesConfig>,
+    pub events: Vec<GameEvent>,
+}
+
+impl GameStore {
+    /// A fresh game to show until the saved one has been loaded.
+    fn new() -> Self {
+        let upgrades_config = Rc::new(load_upgrades_config());
+        Self {
+            state: GameState::new(&upgrades_config, js_sys::Date::now() as u64),
+            upgrades_config,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl Reducible for GameStore {
+    type Action = GameAction;
+
+    fn reduce(self: Rc<Self>, action: GameAction) -> Rc<Self> {
+        let (state, new_events) = reduce(&self.state, &action, &self.upgrades_config);
+        if new_events.is_empty() && state == self.state {
+            return self;
+        }
+
+        let mut events = self.events.clone();
+        events.extend(new_events);
+        let overflow = events.len().saturating_sub(EVENT_LOG_CAPACITY);
+        events.drain(..overflow);
+
+        Rc::new(Self {
+            state,
+            upgrades_config: self.upgrades_config.clone(),
+            events,
+        })
+    }
+}
+
 pub struct GameStateHandle {
-    pub state: UseStateHandle<GameState>,
+    pub store: UseReducerHandle<GameStore>,
     pub on_action: Callback<GameAction>,
+    pub storage: GameStorage,
+    /// False until the saved game has been read. Nothing is saved before
+    /// then, so the placeholder game cannot overwrite the real one.
+    pub loaded: bool,
+    pub offline_progress: Option<OfflineProgress>,
+    /// What was wrong with the last save loaded, if anything.
+    pub save_problems: UseStateHandle<Option<SaveProblems>>,
 }
 
 #[hook]
 pub fn use_game_state() -> GameStateHandle {
-    let state = use_state(|| GameStorage::load());
+    let store = use_reducer(GameStore::new);
+    let storage = (*use_state(GameStorage::configured)).clone();
+    let loaded = use_state(|| false);
+    let offline_progress = use_state(|| None);
+    let save_problems = use_state(|| None);
+
+    {
+        let store = store.clone();
+        let storage = storage.clone();
+        let loaded = loaded.clone();
+        let offline_progress = offline_progress.clone();
+        let save_problems = save_problems.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                let (state, progress, problems) = storage.load(&store.upgrades_config).await;
+                // Loading also checks unlocks, which offline progress may
+                // have met while the game was closed.
+                store.dispatch(GameAction::Load(Box::new(state)));
+                offline_progress.set(progress);
+                save_problems.set(problems);
+                loaded.set(true);
+            });
+        });
+    }
+
     let on_action = {
-        let state = state.clone();
+        let store = store.clone();
+        let storage = storage.clone();
         Callback::from(move |action: GameAction| {
-            let state = state.clone();
-            match action {
-                GameAction::Reset => {
-                    GameStorage::clear(); // Clear storage first
-                    state.set(GameState::new()); // Set completely new state
-                }
-                _ => {
-                    // Handle all other actions by modifying existing state
-                    state.set({
-                        let mut new_state = (*state).clone();
-                        match action {
-                            GameAction::Click => {
-                                new_state.increment_counter();
-                            }
-                            GameAction::BuyUpgrade(upgrade) => {
-                                let cost = new_state.get_upgrade_cost(&upgrade);
-                                if new_state.counter >= cost {
-                                    new_state.counter -= cost;
-                                    new_state.apply_upgrade(upgrade.as_str());
-                                }
-                            }
-                            GameAction::UpdateGameParameter(param) => match param {
-                                GameParameter::BaseMultiplier(value) => {
-                                    new_state.base_multiplier = value;
-                                }
-                                GameParameter::CostScaling(value) => {
-                                    new_state.cost_scaling = value;
-                                }
-                                GameParameter::AutoClickerEfficiency(value) => {
-                                    new_state.auto_clicker_efficiency = value;
-                                }
-                            },
-                            _ => {}
-                        }
-                        new_state
-                    });
-                }
+            if let GameAction::Reset(_) = action {
+                // Clear storage first, so the new game is not saved before
+                // the old one is gone.
+                let store = store.clone();
+                let storage = storage.clone();
+                spawn_local(async move {
+                    storage.clear().await;
+                    store.dispatch(action);
+                });
+                return;
             }
+            store.dispatch(action);
         })
     };
 
-    GameStateHandle { state, on_action }
+    // Prestige progress, achievements and challenges are long-term, so
+    // persist them as soon as they change instead of waiting for the next
+    // auto-save. Only what changes on those milestones is watched:
+    // lifetime earnings grow on every tick and are left to the auto-save.
+    {
+        let state = store.state.clone();
+        let storage = storage.clone();
+        let prestige = &store.state.prestige;
+        let long_term = (
+            *loaded,
+            prestige.points,
+            prestige.resets,
+            store.state.achievements.clone(),
+            store.state.challenges.completed.clone(),
+            store
+                .state
+                .challenges
+                .active
+                .as_ref()
+                .map(|challenge| challenge.id.clone()),
+        );
+        use_effect_with(long_term, move |(loaded, ..)| {
+            if *loaded {
+                spawn_local(async move {
+                    let _ = storage.save(&state).await;
+                });
+            }
+        });
+    }
+
+    GameStateHandle {
+        store,
+        on_action,
+        storage,
+        loaded: *loaded,
+        offline_progress: (*offline_progress).clone(),
+        save_problems,
+    }
 }
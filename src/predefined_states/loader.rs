@@ -1,7 +1,9 @@
-use crate::game::GameState;
-use serde_json::from_str;
+use crate::storage::{parse_save_str, LoadedSave, SaveError};
+use crate::upgrades::UpgradesConfig;
 
-pub fn load_predefined_states() -> Vec<GameState> {
+pub fn load_predefined_states(
+    upgrades_config: &UpgradesConfig,
+) -> Vec<Result<LoadedSave, SaveError>> {
     let mut states = Vec::new();
     let state1_json = include_str!("state1.json");
     let state2_json = include_str!("state2.json");
@@ -11,17 +13,25 @@ pub fn load_predefined_states() -> Vec<GameState> {
     for (index, contents) in paths.iter().enumerate() {
         let path = format!("state{}.json", index + 1);
         log::info!("Attempting to read file: {}", path);
-        match from_str::<GameState>(contents) {
-            Ok(state) => {
-                states.push(state);
+        let result = parse_save_str(contents, upgrades_config).map(|mut loaded| {
+            // Shipped with the game, so there is nothing to verify.
+            loaded.state.statistics.unverified = false;
+            loaded
+        });
+        match &result {
+            Ok(loaded) => {
+                for issue in &loaded.issues {
+                    log::warn!("Repaired {}: {}", path, issue);
+                }
                 log::info!("Loaded state from {}", path);
             }
             Err(err) => {
-                log::error!("Failed to parse JSON from {}: {}", path, err);
+                log::error!("Failed to load {}: {}", path, err);
             }
         }
+        states.push(result);
     }
 
     log::info!("Loaded {} predefined states", states.len());
     states
-}
\ No newline at end of file
+}
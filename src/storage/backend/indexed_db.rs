@@ -0,0 +1,213 @@
+use crate::storage::backend::{history_key, is_history_of, SaveBackend};
+use async_trait::async_trait;
+use futures::future::{FutureExt, LocalBoxFuture, Shared};
+use js_sys::{Array, Function, Promise};
+use serde_json::Value;
+use std::cell::RefCell;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{DomException, IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+/// Bump when the object stores change, and create them in `open`.
+const DATABASE_VERSION: u32 = 1;
+const SAVES_STORE: &str = "saves";
+/// Earlier values kept of each key, oldest dropped first.
+const HISTORY_LENGTH: usize = 5;
+
+/// Opening the database, shared by every use until it is open.
+type OpenDatabase = Shared<LocalBoxFuture<'static, Result<IdbDatabase, String>>>;
+
+/// Keeps saves in IndexedDB, which allows far larger saves than
+/// LocalStorage, and keeps a short history of earlier saves under
+/// [`history_key`]s. Saves are stored as JSON text.
+pub struct IndexedDbBackend {
+    name: String,
+    /// Opened on first use, then kept open. Uses while it is still opening
+    /// wait for the same open instead of starting their own.
+    database: RefCell<Option<OpenDatabase>>,
+}
+
+impl IndexedDbBackend {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            database: RefCell::new(None),
+        }
+    }
+
+    async fn database(&self) -> Result<IdbDatabase, String> {
+        let database = self
+            .database
+            .borrow_mut()
+            .get_or_insert_with(|| open(self.name.clone()).boxed_local().shared())
+            .clone();
+        let result = database.await;
+        if result.is_err() {
+            // Try again on the next use rather than failing for good.
+            self.database.borrow_mut().take();
+        }
+        result
+    }
+
+    async fn store(&self, mode: IdbTransactionMode) -> Result<IdbObjectStore, String> {
+        self.database()
+            .await?
+            .transaction_with_str_and_mode(SAVES_STORE, mode)
+            .and_then(|transaction| transaction.object_store(SAVES_STORE))
+            .map_err(describe)
+    }
+
+    /// Moves the value under `key`, if any, into its history, dropping the
+    /// oldest entries past [`HISTORY_LENGTH`].
+    async fn keep_history(&self, key: &str) -> Result<(), String> {
+        let store = self.store(IdbTransactionMode::Readwrite).await?;
+        let request = store.get(&JsValue::from_str(key)).map_err(describe)?;
+        let previous = wait_for(&request).await?;
+        if previous.is_undefined() {
+            return Ok(());
+        }
+        let history = history_key(key, js_sys::Date::now());
+        let request = store
+            .put_with_key(&previous, &JsValue::from_str(&history))
+            .map_err(describe)?;
+        wait_for(&request).await?;
+
+        let mut history: Vec<String> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|history| is_history_of(history, key))
+            .collect();
+        history.sort();
+        let excess = history.len().saturating_sub(HISTORY_LENGTH);
+        for old in &history[..excess] {
+            self.delete(old).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Opens the database called `name`, creating its object stores if needed.
+async fn open(name: String) -> Result<IdbDatabase, String> {
+    let factory = web_sys::window()
+        .ok_or("no window")?
+        .indexed_db()
+        .map_err(describe)?
+        .ok_or("IndexedDB is not available")?;
+    let request = factory
+        .open_with_u32(&name, DATABASE_VERSION)
+        .map_err(describe)?;
+    let on_upgrade_needed = {
+        let request = request.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            let created = request
+                .result()
+                .and_then(|database| database.dyn_into::<IdbDatabase>())
+                .and_then(|database| {
+                    if database.object_store_names().contains(SAVES_STORE) {
+                        Ok(())
+                    } else {
+                        database.create_object_store(SAVES_STORE).map(|_| ())
+                    }
+                });
+            if let Err(error) = created {
+                log::error!("Failed to set up the save database: {}", describe(error));
+            }
+        })
+    };
+    request.set_onupgradeneeded(Some(on_upgrade_needed.as_ref().unchecked_ref()));
+    let database = wait_for(&request).await;
+    request.set_onupgradeneeded(None);
+    database?
+        .dyn_into::<IdbDatabase>()
+        .map_err(|_| "IndexedDB did not open a database".to_string())
+}
+
+#[async_trait(?Send)]
+impl SaveBackend for IndexedDbBackend {
+    fn name(&self) -> &'static str {
+        "IndexedDB"
+    }
+
+    async fn load(&self, key: &str) -> Result<Option<Value>, String> {
+        let store = self.store(IdbTransactionMode::Readonly).await?;
+        let request = store.get(&JsValue::from_str(key)).map_err(describe)?;
+        match wait_for(&request).await?.as_string() {
+            Some(json) => serde_json::from_str(&json)
+                .map(Some)
+                .map_err(|e| e.to_string()),
+            None => Ok(None),
+        }
+    }
+
+    async fn save(&self, key: &str, value: &Value) -> Result<(), String> {
+        let json = serde_json::to_string(value).map_err(|e| e.to_string())?;
+        if let Err(error) = self.keep_history(key).await {
+            log::error!("Failed to keep the previous save of {}: {}", key, error);
+        }
+        let store = self.store(IdbTransactionMode::Readwrite).await?;
+        let request = store
+            .put_with_key(&JsValue::from_str(&json), &JsValue::from_str(key))
+            .map_err(describe)?;
+        wait_for(&request).await.map(|_| ())
+    }
+
+    async fn list(&self) -> Result<Vec<String>, String> {
+        let store = self.store(IdbTransactionMode::Readonly).await?;
+        let request = store.get_all_keys().map_err(describe)?;
+        let keys = Array::from(&wait_for(&request).await?);
+        Ok(keys.iter().filter_map(|key| key.as_string()).collect())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        let store = self.store(IdbTransactionMode::Readwrite).await?;
+        let request = store.delete(&JsValue::from_str(key)).map_err(describe)?;
+        wait_for(&request).await.map(|_| ())
+    }
+}
+
+/// Resolves with the request's result once it succeeds.
+async fn wait_for(request: &IdbRequest) -> Result<JsValue, String> {
+    // The handlers have to outlive the request, so they are kept here until
+    // it settles.
+    let mut handlers = None;
+    let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+        let on_success = {
+            let request = request.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                let result = request.result().unwrap_or(JsValue::UNDEFINED);
+                let _ = resolve.call1(&JsValue::NULL, &result);
+            })
+        };
+        let on_error = {
+            let request = request.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                let error = request
+                    .error()
+                    .ok()
+                    .flatten()
+                    .map(JsValue::from)
+                    .unwrap_or(JsValue::UNDEFINED);
+                let _ = reject.call1(&JsValue::NULL, &error);
+            })
+        };
+        request.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+        request.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        handlers = Some((on_success, on_error));
+    });
+    let result = JsFuture::from(promise).await.map_err(describe);
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+    drop(handlers);
+    result
+}
+
+fn describe(error: JsValue) -> String {
+    if let Some(exception) = error.dyn_ref::<DomException>() {
+        return exception.message();
+    }
+    error
+        .as_string()
+        .unwrap_or_else(|| "unknown IndexedDB error".to_string())
+}
@@ -0,0 +1,47 @@
+use crate::storage::backend::SaveBackend;
+use async_trait::async_trait;
+use gloo_storage::errors::StorageError;
+use gloo_storage::{LocalStorage, Storage};
+use serde_json::Value;
+
+/// LocalStorage is shared with everything else on the origin, so only keys
+/// starting with this are listed as the game's.
+const KEY_PREFIX: &str = "idle_game";
+
+/// Keeps saves in the browser's LocalStorage: synchronous and limited to a
+/// few megabytes, but available everywhere.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalStorageBackend;
+
+#[async_trait(?Send)]
+impl SaveBackend for LocalStorageBackend {
+    fn name(&self) -> &'static str {
+        "LocalStorage"
+    }
+
+    async fn load(&self, key: &str) -> Result<Option<Value>, String> {
+        match LocalStorage::get::<Value>(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(StorageError::KeyNotFound(_)) => Ok(None),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    async fn save(&self, key: &str, value: &Value) -> Result<(), String> {
+        LocalStorage::set(key, value).map_err(|e| e.to_string())
+    }
+
+    async fn list(&self) -> Result<Vec<String>, String> {
+        let storage = LocalStorage::raw();
+        let length = storage.length().map_err(|_| "cannot read LocalStorage")?;
+        Ok((0..length)
+            .filter_map(|index| storage.key(index).ok().flatten())
+            .filter(|key| key.starts_with(KEY_PREFIX))
+            .collect())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        LocalStorage::delete(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::storage::backend::SaveBackend;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Keeps saves in memory, for native tests and for playing without
+/// persisting anything. Clones share the same values.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    values: Rc<RefCell<BTreeMap<String, Value>>>,
+}
+
+#[async_trait(?Send)]
+impl SaveBackend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "Memory"
+    }
+
+    async fn load(&self, key: &str) -> Result<Option<Value>, String> {
+        Ok(self.values.borrow().get(key).cloned())
+    }
+
+    async fn save(&self, key: &str, value: &Value) -> Result<(), String> {
+        self.values
+            .borrow_mut()
+            .insert(key.to_string(), value.clone());
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<String>, String> {
+        Ok(self.values.borrow().keys().cloned().collect())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        self.values.borrow_mut().remove(key);
+        Ok(())
+    }
+}
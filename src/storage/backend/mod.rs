@@ -0,0 +1,59 @@
+mod indexed_db;
+mod local_storage;
+mod memory;
+
+pub use indexed_db::IndexedDbBackend;
+pub use local_storage::LocalStorageBackend;
+pub use memory::MemoryBackend;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::rc::Rc;
+
+/// Name of the IndexedDB database saves are kept in.
+const DATABASE_NAME: &str = "idle_game";
+
+/// Where a backend that keeps earlier values of `key` keeps the one
+/// replaced at `saved_at` (milliseconds since the epoch).
+pub fn history_key(key: &str, saved_at: f64) -> String {
+    // Zero-padded, so that history keys sort by time.
+    format!("{}@{:015}", key, saved_at as u64)
+}
+
+/// Whether `history_key` holds an earlier value of `key`.
+pub fn is_history_of(history_key: &str, key: &str) -> bool {
+    history_key
+        .strip_prefix(key)
+        .is_some_and(|rest| rest.starts_with('@'))
+}
+
+/// Somewhere saves can be kept: JSON values under string keys. Methods are
+/// async so that backends like IndexedDB, whose API is asynchronous, fit.
+#[async_trait(?Send)]
+pub trait SaveBackend {
+    /// Shown to the player, e.g. "IndexedDB".
+    fn name(&self) -> &'static str;
+
+    /// The value stored under `key`, or `None` if there is none.
+    async fn load(&self, key: &str) -> Result<Option<Value>, String>;
+
+    /// Stores `value` under `key`. Backends with history keep the value it
+    /// replaces under a [`history_key`].
+    async fn save(&self, key: &str, value: &Value) -> Result<(), String>;
+
+    /// Every key this backend holds a value under, history keys included.
+    async fn list(&self) -> Result<Vec<String>, String>;
+
+    /// Removes `key`; removing a key that holds nothing is not an error.
+    async fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+/// The backend picked through `IDLE_SAVE_BACKEND` when the game is built:
+/// `local_storage` (the default), `indexed_db` or `memory`.
+pub fn configured_backend() -> Rc<dyn SaveBackend> {
+    match option_env!("IDLE_SAVE_BACKEND") {
+        Some("indexed_db") => Rc::new(IndexedDbBackend::new(DATABASE_NAME)),
+        Some("memory") => Rc::new(MemoryBackend::default()),
+        _ => Rc::new(LocalStorageBackend),
+    }
+}
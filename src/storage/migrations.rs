@@ -0,0 +1,239 @@
+use crate::game::GameState;
+use crate::storage::integrity::{sign_state, verify_state};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Version of the save format written by this build. Bump it whenever a
+/// change to `GameState` would stop older saves from deserializing, and add
+/// the migration from the previous version to [`MIGRATIONS`].
+pub const SAVE_VERSION: u32 = 3;
+
+/// First version whose envelope carries a signature. Older saves cannot be
+/// verified, so games loaded from them are marked as unverified.
+const SIGNED_SINCE_VERSION: u32 = 2;
+
+/// The only resource there was while saves kept a single `counter`.
+const LEGACY_RESOURCE: &str = "gold";
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// `MIGRATIONS[n]` upgrades a version `n` state to version `n + 1`.
+const MIGRATIONS: [Migration; SAVE_VERSION as usize] =
+    [counter_to_resources, add_signature, drop_clicks_per_second];
+
+/// What gets stored: the game state tagged with the format it was written in
+/// and signed, so edits made outside the game can be told apart.
+#[derive(Serialize, Deserialize)]
+pub struct SaveEnvelope {
+    pub version: u32,
+    pub state: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl SaveEnvelope {
+    pub fn current(state: &GameState) -> Result<Self, String> {
+        let state = serde_json::to_value(state).map_err(|e| e.to_string())?;
+        Ok(Self {
+            version: SAVE_VERSION,
+            signature: Some(sign_state(&state)),
+            state,
+        })
+    }
+}
+
+/// Reads a save of any version, migrating it step by step to the current one.
+/// Saves from before the envelope are bare states: version 0 if they still
+/// have a `counter`, version 1 otherwise. A signed save whose signature does
+/// not match is still loaded, but marked as modified in its statistics; an
+/// unsigned one is marked as unverified, since an edited save can pass
+/// itself off as older.
+pub fn migrate_save(save: Value) -> Result<GameState, String> {
+    let (version, mut state, signature) = match save {
+        Value::Object(mut object) if object.contains_key("version") => {
+            let version = object
+                .remove("version")
+                .and_then(|version| version.as_u64())
+                .ok_or("save version is not a number")?;
+            let state = object.remove("state").ok_or("save has no state")?;
+            let signature = object
+                .remove("signature")
+                .and_then(|signature| signature.as_str().map(str::to_string));
+            (version as u32, state, signature)
+        }
+        Value::Object(object) if object.contains_key("counter") => (0, Value::Object(object), None),
+        Value::Object(object) => (1, Value::Object(object), None),
+        _ => return Err("save is not a JSON object".to_string()),
+    };
+    if version > SAVE_VERSION {
+        return Err(format!(
+            "save version {} is newer than this game supports ({})",
+            version, SAVE_VERSION
+        ));
+    }
+    let unverified = version < SIGNED_SINCE_VERSION;
+    let modified =
+        !unverified && !signature.is_some_and(|signature| verify_state(&state, &signature));
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        state = migration(state)
+            .map_err(|error| format!("cannot migrate save from version {}: {}", from, error))?;
+    }
+    let mut state: GameState =
+        serde_json::from_value(state).map_err(|error| format!("invalid save: {}", error))?;
+    if modified {
+        log::warn!("Save signature does not match, marking the game as modified");
+        state.statistics.modified = true;
+    }
+    if unverified {
+        log::info!(
+            "Save from version {} is unsigned, marking the game as unverified",
+            version
+        );
+        state.statistics.unverified = true;
+    }
+    Ok(state)
+}
+
+/// Version 0 to 1: the single `counter` became the primary entry of
+/// `resources`, and the fixed upgrade fields went away.
+fn counter_to_resources(mut state: Value) -> Result<Value, String> {
+    let object = state.as_object_mut().ok_or("state is not a JSON object")?;
+    let counter = object.remove("counter").ok_or("state has no counter")?;
+    let mut resources = Map::new();
+    resources.insert(LEGACY_RESOURCE.to_string(), counter);
+    object.insert("resources".to_string(), Value::Object(resources));
+    object.remove("x2_upgrade_cost");
+    object.remove("easy_mode");
+    Ok(state)
+}
+
+/// Version 1 to 2: envelopes gained a signature; the state is unchanged.
+fn add_signature(state: Value) -> Result<Value, String> {
+    Ok(state)
+}
+
+/// Version 2 to 3: the stored `clicks_per_second` went away; it is worked
+/// out from the upgrades instead.
+fn drop_clicks_per_second(mut state: Value) -> Result<Value, String> {
+    let object = state.as_object_mut().ok_or("state is not a JSON object")?;
+    object.remove("clicks_per_second");
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::BigNum;
+
+    fn migrate_fixture(json: &str) -> Result<GameState, String> {
+        migrate_save(serde_json::from_str(json).expect("fixture is valid JSON"))
+    }
+
+    #[test]
+    fn migrates_baseline_counter_save() {
+        let state = migrate_fixture(include_str!("fixtures/v0_counter.json")).unwrap();
+        assert_eq!(state.resources.get("gold"), BigNum::from(10));
+        assert_eq!(state.upgrades.level("auto_clicker"), 2);
+        assert_eq!(state.upgrades.level("click_multiplier"), 1);
+        assert_eq!(state.base_multiplier, 1.5);
+    }
+
+    #[test]
+    fn migrates_big_number_counter_save() {
+        let state = migrate_fixture(include_str!("fixtures/v0_big_counter.json")).unwrap();
+        assert_eq!(state.resources.get("gold"), BigNum::new(1.5, 40));
+        assert_eq!(state.upgrades.level("auto_clicker"), 12);
+    }
+
+    #[test]
+    fn reads_unversioned_resources_save() {
+        let state = migrate_fixture(include_str!("fixtures/v1_unversioned.json")).unwrap();
+        assert_eq!(state.resources.get("gold"), BigNum::from(2500));
+        assert_eq!(state.resources.get("gems"), BigNum::from(3));
+        assert_eq!(state.prestige.resets, 1);
+    }
+
+    #[test]
+    fn reads_current_envelope() {
+        let state = migrate_fixture(include_str!("fixtures/v1_envelope.json")).unwrap();
+        assert_eq!(state.resources.get("gold"), BigNum::from(120));
+        assert!(state.achievements.contains("first_click"));
+        assert_eq!(state.statistics.clicks, 42);
+    }
+
+    #[test]
+    fn round_trips_through_envelope() {
+        let state = migrate_fixture(include_str!("fixtures/v1_unversioned.json")).unwrap();
+        let saved = serde_json::to_value(SaveEnvelope::current(&state).unwrap()).unwrap();
+        assert_eq!(migrate_save(saved).unwrap(), state);
+    }
+
+    #[test]
+    fn flags_edited_signed_saves() {
+        let mut state = migrate_fixture(include_str!("fixtures/v1_envelope.json")).unwrap();
+        state.statistics.unverified = false;
+        let mut saved = serde_json::to_value(SaveEnvelope::current(&state).unwrap()).unwrap();
+        let loaded = migrate_save(saved.clone()).unwrap();
+        assert!(!loaded.statistics.modified);
+        assert!(!loaded.statistics.unverified);
+
+        saved["state"]["resources"]["gold"] = Value::from("1e9");
+        let edited = migrate_save(saved.clone()).unwrap();
+        assert!(edited.statistics.modified);
+        assert_eq!(edited.resources.get("gold"), BigNum::new(1.0, 9));
+
+        saved.as_object_mut().unwrap().remove("signature");
+        assert!(migrate_save(saved).unwrap().statistics.modified);
+    }
+
+    #[test]
+    fn drops_stored_clicks_per_second() {
+        let state = migrate_fixture(include_str!("fixtures/v1_envelope.json")).unwrap();
+        let saved = serde_json::to_value(SaveEnvelope::current(&state).unwrap()).unwrap();
+        assert!(saved["state"].get("clicks_per_second").is_none());
+
+        // Version 2 saves still carry it, and are signed with it.
+        let mut state = saved["state"].clone();
+        state["clicks_per_second"] = Value::from(3);
+        let signature = sign_state(&state);
+        let v2 = serde_json::json!({ "version": 2, "state": state, "signature": signature });
+        let loaded = migrate_save(v2).unwrap();
+        assert!(!loaded.statistics.modified);
+    }
+
+    #[test]
+    fn flags_edited_saves_passed_off_as_unsigned() {
+        let mut state = migrate_fixture(include_str!("fixtures/v1_envelope.json")).unwrap();
+        state.statistics.unverified = false;
+        let mut saved = serde_json::to_value(SaveEnvelope::current(&state).unwrap()).unwrap();
+        saved["state"]["resources"]["gold"] = Value::from("1e9");
+
+        let mut downgraded = saved.clone();
+        downgraded["version"] = Value::from(1);
+        downgraded.as_object_mut().unwrap().remove("signature");
+        let loaded = migrate_save(downgraded).unwrap();
+        assert!(loaded.statistics.unverified);
+        assert_eq!(loaded.resources.get("gold"), BigNum::new(1.0, 9));
+
+        let bare = saved["state"].clone();
+        assert!(migrate_save(bare).unwrap().statistics.unverified);
+    }
+
+    #[test]
+    fn marks_saves_from_before_signing_unverified() {
+        let state = migrate_fixture(include_str!("fixtures/v1_envelope.json")).unwrap();
+        assert!(!state.statistics.modified);
+        assert!(state.statistics.unverified);
+    }
+
+    #[test]
+    fn rejects_newer_and_malformed_saves() {
+        let newer = serde_json::json!({ "version": SAVE_VERSION + 1, "state": {} });
+        assert!(migrate_save(newer).unwrap_err().contains("newer"));
+        assert!(migrate_save(serde_json::json!([1, 2])).is_err());
+        let broken = serde_json::json!({ "counter": 5 });
+        assert!(migrate_save(broken)
+            .unwrap_err()
+            .starts_with("invalid save"));
+    }
+}
@@ -1,40 +1,561 @@
-use crate::game::GameState;
+mod backend;
+mod integrity;
+mod migrations;
+mod save_string;
+mod slots;
+mod validation;
+
+pub use migrations::{migrate_save, SaveEnvelope};
+pub use slots::SaveSlots;
+pub use validation::{parse_save, parse_save_str, LoadedSave, SaveError, SaveProblems};
+
+use backend::{configured_backend, is_history_of, SaveBackend};
+use save_string::{decode_save_string, encode_save_string};
+use slots::DEFAULT_SLOT;
+
+use crate::game::{Clock, GameState, OfflineProgress, Rng};
 use crate::predefined_states::load_predefined_states;
+use crate::upgrades::UpgradesConfig;
+use crate::utils::clock::BrowserClock;
 use crate::utils::file::{load_from_file, save_to_file};
-use gloo_storage::{LocalStorage, Storage};
-use serde::{Deserialize, Serialize};
-use yew::UseStateHandle;
+use futures::lock::Mutex;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
+use yew::Callback;
 
 const SAVE_KEY: &str = "idle_game_save";
+/// Where the index of save slots is kept.
+const SLOTS_KEY: &str = "idle_game_slots";
+
+/// Where a slot's save is kept. The default slot keeps the key saves had
+/// before there were slots.
+fn slot_key(id: &str) -> String {
+    if id == DEFAULT_SLOT {
+        SAVE_KEY.to_string()
+    } else {
+        format!("{}_{}", SAVE_KEY, id)
+    }
+}
 
-#[derive(Serialize, Deserialize)]
-pub struct GameStorage;
+/// Where a slot's save that could not be migrated is kept instead of being lost.
+fn backup_key(id: &str) -> String {
+    format!("{}_backup", slot_key(id))
+}
+
+/// Saves and loads the game through a [`SaveBackend`]. Cheap to clone;
+/// clones share the backend.
+#[derive(Clone)]
+pub struct GameStorage {
+    backend: Rc<dyn SaveBackend>,
+    clock: Rc<dyn Clock>,
+    /// The slot the running game was loaded from, which saves go to. Empty
+    /// from switching slots until the new slot is loaded, so the old game
+    /// cannot be saved over the new slot's.
+    playing: Rc<RefCell<Option<String>>>,
+    /// Held through each operation, so that one reading and rewriting the
+    /// slot index cannot interleave with another and undo its change.
+    queue: Rc<Mutex<()>>,
+}
+
+impl PartialEq for GameStorage {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.backend, &other.backend)
+    }
+}
 
 impl GameStorage {
-    pub fn load() -> GameState {
-        LocalStorage::get(SAVE_KEY).unwrap_or_else(|_| {
-            let predefined_states = load_predefined_states();
-            predefined_states
-                .get(0)
-                .cloned()
-                .unwrap_or_else(GameState::new)
-        })
+    pub fn new(backend: Rc<dyn SaveBackend>, clock: Rc<dyn Clock>) -> Self {
+        Self {
+            backend,
+            clock,
+            playing: Rc::new(RefCell::new(None)),
+            queue: Rc::new(Mutex::new(())),
+        }
+    }
+
+    /// Storage on the backend chosen when the game was built.
+    pub fn configured() -> Self {
+        Self::new(configured_backend(), Rc::new(BrowserClock))
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// Loads the active slot's saved game, crediting production earned since
+    /// it was last saved. Anything wrong with the save is returned for the
+    /// player to see. Saves go to this slot from now on, unless its save
+    /// could not be read: it may still be there, so nothing is saved over it.
+    pub async fn load(
+        &self,
+        upgrades_config: &UpgradesConfig,
+    ) -> (GameState, Option<OfflineProgress>, Option<SaveProblems>) {
+        let _queued = self.queue.lock().await;
+        let active = self.slots().await.active;
+        let save = match self.backend.load(&slot_key(&active)).await {
+            Ok(save) => save,
+            Err(error) => {
+                log::error!("Failed to read saved game: {}", error);
+                *self.playing.borrow_mut() = None;
+                let problems = SaveProblems::Failed {
+                    source: "Saved game".to_string(),
+                    error: SaveError::Unreadable(error),
+                };
+                return (self.new_game(upgrades_config), None, Some(problems));
+            }
+        };
+        let loaded = self.load_save(&active, save, upgrades_config).await;
+        *self.playing.borrow_mut() = Some(active);
+        loaded
+    }
+
+    async fn load_save(
+        &self,
+        active: &str,
+        save: Option<Value>,
+        upgrades_config: &UpgradesConfig,
+    ) -> (GameState, Option<OfflineProgress>, Option<SaveProblems>) {
+        let Some(save) = save else {
+            return (self.new_game(upgrades_config), None, None);
+        };
+        let result = parse_save(save.clone(), upgrades_config);
+        let problems = SaveProblems::from_result("Saved game", &result);
+        match result {
+            Ok(LoadedSave { mut state, .. }) => {
+                let offline_progress =
+                    state.apply_offline_progress(self.clock.as_ref(), upgrades_config);
+                (state, offline_progress, problems)
+            }
+            Err(error) => {
+                log::error!("Failed to load saved game, backing it up: {}", error);
+                if let Err(error) = self.backend.save(&backup_key(active), &save).await {
+                    log::error!("Failed to back up saved game: {}", error);
+                }
+                (self.new_game(upgrades_config), None, problems)
+            }
+        }
+    }
+
+    /// The first predefined state that loads, or a blank game if none does.
+    fn new_game(&self, upgrades_config: &UpgradesConfig) -> GameState {
+        // Each new player gets their own stream of random events.
+        let seed = self.clock.now() as u64;
+        load_predefined_states(upgrades_config)
+            .into_iter()
+            .find_map(Result::ok)
+            .map(|loaded| GameState {
+                rng: Rng::new(seed),
+                ..loaded.state
+            })
+            .unwrap_or_else(|| GameState::new(upgrades_config, seed))
     }
 
-    pub fn save(state: &GameState) -> Result<(), String> {
-        LocalStorage::set(SAVE_KEY, state).map_err(|e| format!("Failed to save game: {}", e))
+    /// Saves to the slot the running game was loaded from and updates its
+    /// metadata. Fails while no slot is loaded.
+    pub async fn save(&self, state: &GameState) -> Result<(), String> {
+        let _queued = self.queue.lock().await;
+        let playing = self.playing.borrow().clone();
+        let id = playing.ok_or("No save slot is loaded to save to")?;
+        let mut state = state.clone();
+        state.last_saved = self.clock.now();
+        let envelope = serde_json::to_value(SaveEnvelope::current(&state)?)
+            .map_err(|e| format!("Failed to save game: {}", e))?;
+        let mut slots = self.slots().await;
+        let slot = slots.get_mut(&id)?;
+        self.backend
+            .save(&slot_key(&id), &envelope)
+            .await
+            .map_err(|e| format!("Failed to save game: {}", e))?;
+        slot.record(&state);
+        self.set_slots(&slots).await
     }
 
-    pub fn clear() {
-        LocalStorage::delete(SAVE_KEY);
+    /// Deletes the active slot's save; the slot itself stays.
+    pub async fn clear(&self) {
+        let _queued = self.queue.lock().await;
+        let mut slots = self.slots().await;
+        let slot = slots.active_mut();
+        if let Err(error) = self.backend.delete(&slot_key(&slot.id)).await {
+            log::error!("Failed to clear game state: {}", error);
+        }
+        slot.forget_progress();
+        if let Err(error) = self.set_slots(&slots).await {
+            log::error!("{}", error);
+        }
         log::info!("Game state cleared");
     }
 
+    /// The save slots, or just the default one if none were made yet.
+    pub async fn slots(&self) -> SaveSlots {
+        match self.backend.load(SLOTS_KEY).await {
+            Ok(Some(slots)) => serde_json::from_value(slots).unwrap_or_default(),
+            _ => SaveSlots::default(),
+        }
+    }
+
+    async fn set_slots(&self, slots: &SaveSlots) -> Result<(), String> {
+        let slots = serde_json::to_value(slots).map_err(|e| e.to_string())?;
+        self.backend
+            .save(SLOTS_KEY, &slots)
+            .await
+            .map_err(|e| format!("Failed to save slots: {}", e))
+    }
+
+    /// Applies `change` to the slot index and stores it, returning the result.
+    /// Callers hold the queue.
+    async fn update_slots(
+        &self,
+        change: impl FnOnce(&mut SaveSlots) -> Result<(), String>,
+    ) -> Result<SaveSlots, String> {
+        let mut slots = self.slots().await;
+        change(&mut slots)?;
+        self.set_slots(&slots).await?;
+        Ok(slots)
+    }
+
+    /// Adds an empty slot; switching to it starts a new game.
+    pub async fn create_slot(&self, name: &str) -> Result<SaveSlots, String> {
+        let _queued = self.queue.lock().await;
+        self.update_slots(|slots| slots.create(name).map(|_| ()))
+            .await
+    }
+
+    pub async fn rename_slot(&self, id: &str, name: &str) -> Result<SaveSlots, String> {
+        let _queued = self.queue.lock().await;
+        self.update_slots(|slots| slots.rename(id, name)).await
+    }
+
+    /// Copies a slot and its save into a new slot.
+    pub async fn duplicate_slot(&self, id: &str) -> Result<SaveSlots, String> {
+        let _queued = self.queue.lock().await;
+        let mut slots = self.slots().await;
+        let copy = slots.duplicate(id)?;
+        let save = self
+            .backend
+            .load(&slot_key(id))
+            .await
+            .map_err(|e| format!("Failed to read save: {}", e))?;
+        if let Some(save) = save {
+            self.backend
+                .save(&slot_key(&copy), &save)
+                .await
+                .map_err(|e| format!("Failed to copy save: {}", e))?;
+        }
+        self.set_slots(&slots).await?;
+        Ok(slots)
+    }
+
+    /// Deletes a slot and its save, with any history of it. Deleting the active slot switches to
+    /// another one, which the caller then has to load.
+    pub async fn delete_slot(&self, id: &str) -> Result<SaveSlots, String> {
+        let _queued = self.queue.lock().await;
+        let slots = self.update_slots(|slots| slots.delete(id)).await?;
+        self.stop_playing(id);
+        let slot_keys = [slot_key(id), backup_key(id)];
+        let keys = self
+            .backend
+            .list()
+            .await
+            .map_err(|e| format!("Failed to list saves: {}", e))?;
+        let of_slot = |key: &String| {
+            slot_keys
+                .iter()
+                .any(|slot_key| key == slot_key || is_history_of(key, slot_key))
+        };
+        for key in keys.iter().filter(|key| of_slot(key)) {
+            if let Err(error) = self.backend.delete(key).await {
+                log::error!("Failed to delete {}: {}", key, error);
+            }
+        }
+        Ok(slots)
+    }
+
+    /// Makes another slot the active one. The caller saves the running game
+    /// first and loads the new slot after; nothing is saved in between.
+    pub async fn switch_slot(&self, id: &str) -> Result<SaveSlots, String> {
+        let _queued = self.queue.lock().await;
+        let slots = self.update_slots(|slots| slots.switch(id)).await?;
+        let playing = self.playing.borrow().clone();
+        if let Some(playing) = playing {
+            if playing != id {
+                self.stop_playing(&playing);
+            }
+        }
+        Ok(slots)
+    }
+
+    /// Stops saving the running game if it was loaded from `id`.
+    fn stop_playing(&self, id: &str) {
+        let mut playing = self.playing.borrow_mut();
+        if playing.as_deref() == Some(id) {
+            *playing = None;
+        }
+    }
+
     pub fn save_to_file(state: &GameState, file_path: &str) {
-        save_to_file(state, file_path);
+        match SaveEnvelope::current(state) {
+            Ok(envelope) => save_to_file(&envelope, file_path),
+            Err(error) => log::error!("Failed to export game state: {}", error),
+        }
+    }
+
+    /// The game as a compact save string, small enough to paste into a bug
+    /// report.
+    pub fn to_save_string(state: &GameState) -> Result<String, String> {
+        encode_save_string(&SaveEnvelope::current(state)?)
+    }
+
+    /// Reads a pasted save: a save string, or a save exported as JSON.
+    pub fn from_save_string(
+        text: &str,
+        upgrades_config: &UpgradesConfig,
+    ) -> Result<LoadedSave, SaveError> {
+        if text.trim_start().starts_with('{') {
+            return parse_save_str(text, upgrades_config);
+        }
+        let save = decode_save_string(text).map_err(SaveError::Unreadable)?;
+        parse_save(save, upgrades_config)
+    }
+
+    /// Opens a file picker and loads the chosen save, migrating it if it
+    /// was exported by an older version and repairing what it can.
+    pub fn load_from_file(
+        upgrades_config: Rc<UpgradesConfig>,
+        on_load: Callback<Result<LoadedSave, SaveError>>,
+    ) {
+        load_from_file(Callback::from(move |contents: String| {
+            let result = parse_save_str(&contents, &upgrades_config);
+            if let Err(error) = &result {
+                log::error!("Failed to load game state from file: {}", error);
+            }
+            on_load.emit(result);
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::BigNum;
+    use crate::upgrades::load_upgrades_config;
+    use async_trait::async_trait;
+    use backend::{history_key, MemoryBackend};
+    use futures::executor::block_on;
+    use std::task::Poll;
+
+    struct FixedClock(f64);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> f64 {
+            self.0
+        }
+    }
+
+    /// Yields once before each operation, as a real asynchronous backend
+    /// would, so that operations running at the same time interleave.
+    struct YieldingBackend(MemoryBackend);
+
+    async fn yield_now() {
+        let mut yielded = false;
+        futures::future::poll_fn(|cx| {
+            if yielded {
+                return Poll::Ready(());
+            }
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await
+    }
+
+    #[async_trait(?Send)]
+    impl SaveBackend for YieldingBackend {
+        fn name(&self) -> &'static str {
+            "Yielding"
+        }
+
+        async fn load(&self, key: &str) -> Result<Option<Value>, String> {
+            yield_now().await;
+            self.0.load(key).await
+        }
+
+        async fn save(&self, key: &str, value: &Value) -> Result<(), String> {
+            yield_now().await;
+            self.0.save(key, value).await
+        }
+
+        async fn list(&self) -> Result<Vec<String>, String> {
+            yield_now().await;
+            self.0.list().await
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), String> {
+            yield_now().await;
+            self.0.delete(key).await
+        }
+    }
+
+    /// Fails to read saves, as a backend holding a corrupt one would, but
+    /// reads the slot index.
+    struct UnreadableBackend(MemoryBackend);
+
+    #[async_trait(?Send)]
+    impl SaveBackend for UnreadableBackend {
+        fn name(&self) -> &'static str {
+            "Unreadable"
+        }
+
+        async fn load(&self, key: &str) -> Result<Option<Value>, String> {
+            if key == SLOTS_KEY {
+                return self.0.load(key).await;
+            }
+            Err("corrupt save".to_string())
+        }
+
+        async fn save(&self, key: &str, value: &Value) -> Result<(), String> {
+            self.0.save(key, value).await
+        }
+
+        async fn list(&self) -> Result<Vec<String>, String> {
+            self.0.list().await
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), String> {
+            self.0.delete(key).await
+        }
+    }
+
+    fn storage(backend: &MemoryBackend) -> GameStorage {
+        GameStorage::new(Rc::new(backend.clone()), Rc::new(FixedClock(1_000_000.0)))
+    }
+
+    #[test]
+    fn saves_and_loads_through_a_backend() {
+        let config = load_upgrades_config();
+        let backend = MemoryBackend::default();
+        let storage = storage(&backend);
+        block_on(async {
+            let (mut state, _, problems) = storage.load(&config).await;
+            assert!(problems.is_none());
+            state.resources.add("gold", BigNum::from(42));
+            let gold = state.resources.get("gold");
+            storage.save(&state).await.unwrap();
+
+            let (loaded, offline_progress, problems) = storage.load(&config).await;
+            assert!(problems.is_none());
+            assert!(offline_progress.is_none());
+            assert_eq!(loaded.resources.get("gold"), gold);
+            let slots = storage.slots().await;
+            assert_eq!(slots.slots[0].resources.get("gold"), gold);
+        });
+        let keys = block_on(backend.list()).unwrap();
+        assert_eq!(keys, [SAVE_KEY, SLOTS_KEY]);
+    }
+
+    #[test]
+    fn duplicates_and_deletes_slots_with_their_saves() {
+        let config = load_upgrades_config();
+        let backend = MemoryBackend::default();
+        let storage = storage(&backend);
+        block_on(async {
+            let (mut state, _, _) = storage.load(&config).await;
+            state.resources.add("gold", BigNum::from(7));
+            let gold = state.resources.get("gold");
+            storage.save(&state).await.unwrap();
+
+            let slots = storage.duplicate_slot(DEFAULT_SLOT).await.unwrap();
+            let copy = slots.slots[1].id.clone();
+            storage.switch_slot(&copy).await.unwrap();
+            let (loaded, _, _) = storage.load(&config).await;
+            assert_eq!(loaded.resources.get("gold"), gold);
+
+            let earlier = history_key(&slot_key(&copy), 1_000_000.0);
+            let save = backend.load(&slot_key(&copy)).await.unwrap().unwrap();
+            backend.save(&earlier, &save).await.unwrap();
+
+            let slots = storage.delete_slot(&copy).await.unwrap();
+            assert_eq!(slots.active, DEFAULT_SLOT);
+            assert_eq!(backend.load(&slot_key(&copy)).await.unwrap(), None);
+            assert_eq!(backend.load(&earlier).await.unwrap(), None);
+            assert!(backend.load(SAVE_KEY).await.unwrap().is_some());
+        });
+    }
+
+    #[test]
+    fn keeps_saves_it_could_not_read() {
+        let config = load_upgrades_config();
+        let backend = MemoryBackend::default();
+        let storage = storage(&backend);
+        block_on(async {
+            let (mut state, _, _) = storage.load(&config).await;
+            state.resources.add("gold", BigNum::from(42));
+            storage.save(&state).await.unwrap();
+            let saved = backend.load(SAVE_KEY).await.unwrap();
+
+            let unreadable = GameStorage::new(
+                Rc::new(UnreadableBackend(backend.clone())),
+                Rc::new(FixedClock(1_000_000.0)),
+            );
+            let (new_game, _, problems) = unreadable.load(&config).await;
+            assert!(matches!(problems, Some(SaveProblems::Failed { .. })));
+            assert!(unreadable.save(&new_game).await.is_err());
+            assert_eq!(backend.load(SAVE_KEY).await.unwrap(), saved);
+        });
     }
 
-    pub fn load_from_file(state: UseStateHandle<GameState>) {
-        load_from_file(state);
+    #[test]
+    fn runs_operations_one_at_a_time() {
+        let config = load_upgrades_config();
+        let backend = MemoryBackend::default();
+        let storage = GameStorage::new(
+            Rc::new(YieldingBackend(backend.clone())),
+            Rc::new(FixedClock(1_000_000.0)),
+        );
+        block_on(async {
+            let (mut state, _, _) = storage.load(&config).await;
+            state.resources.add("gold", BigNum::from(7));
+            let (saved, created, duplicated) = futures::join!(
+                storage.save(&state),
+                storage.create_slot("Second"),
+                storage.duplicate_slot(DEFAULT_SLOT),
+            );
+            saved.unwrap();
+            created.unwrap();
+            duplicated.unwrap();
+
+            let slots = storage.slots().await;
+            let names: Vec<_> = slots.slots.iter().map(|slot| slot.name.as_str()).collect();
+            assert_eq!(names, ["Slot 1", "Second", "Slot 1 (copy)"]);
+            let gold = state.resources.get("gold");
+            assert_eq!(slots.slots[0].resources.get("gold"), gold);
+            assert_eq!(slots.slots[2].resources.get("gold"), gold);
+        });
+    }
+
+    #[test]
+    fn saves_nothing_while_switching_slots() {
+        let config = load_upgrades_config();
+        let backend = MemoryBackend::default();
+        let storage = storage(&backend);
+        block_on(async {
+            let (mut state, _, _) = storage.load(&config).await;
+            state.resources.add("gold", BigNum::from(7));
+            storage.save(&state).await.unwrap();
+
+            let slots = storage.create_slot("Second").await.unwrap();
+            let second = slots.slots[1].id.clone();
+            storage.switch_slot(&second).await.unwrap();
+            assert!(storage.save(&state).await.is_err());
+            assert_eq!(backend.load(&slot_key(&second)).await.unwrap(), None);
+
+            let (loaded, _, _) = storage.load(&config).await;
+            assert_ne!(loaded.resources.get("gold"), state.resources.get("gold"));
+            storage.save(&loaded).await.unwrap();
+            assert!(backend.load(&slot_key(&second)).await.unwrap().is_some());
+
+            storage.delete_slot(&second).await.unwrap();
+            assert!(storage.save(&loaded).await.is_err());
+        });
     }
 }
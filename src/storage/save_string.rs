@@ -0,0 +1,116 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Marks text as a save string, so a pasted save is told apart from
+/// anything else that ended up on the clipboard.
+const SAVE_STRING_PREFIX: &str = "IDLE";
+/// Layout of the save string itself: how the payload is compressed and
+/// checked. Independent of the save version inside the payload.
+const SAVE_STRING_FORMAT: u32 = 1;
+/// Largest payload a save string may inflate to, against decompression bombs.
+const MAX_SAVE_BYTES: usize = 4 * 1024 * 1024;
+const COMPRESSION_LEVEL: u8 = 9;
+
+/// Encodes a save as `IDLE<format>:<payload>`, where the payload is the
+/// deflated JSON followed by its CRC-32, in URL-safe base64 so it survives
+/// being pasted into chat and bug trackers.
+pub fn encode_save_string<T: Serialize>(save: &T) -> Result<String, String> {
+    let json = serde_json::to_vec(save).map_err(|e| e.to_string())?;
+    let mut payload = compress_to_vec(&json, COMPRESSION_LEVEL);
+    payload.extend_from_slice(&crc32fast::hash(&json).to_be_bytes());
+    Ok(format!(
+        "{}{}:{}",
+        SAVE_STRING_PREFIX,
+        SAVE_STRING_FORMAT,
+        URL_SAFE_NO_PAD.encode(payload)
+    ))
+}
+
+/// Reverses [`encode_save_string`], returning the save JSON for
+/// [`migrate_save`](crate::storage::migrate_save). Whitespace, such as line
+/// breaks added by a mail client, is ignored.
+pub fn decode_save_string(text: &str) -> Result<Value, String> {
+    let text: String = text.split_whitespace().collect();
+    let (format, payload) = text
+        .strip_prefix(SAVE_STRING_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .ok_or("not a save string")?;
+    let format = format
+        .parse::<u32>()
+        .map_err(|_| format!("invalid save string format '{}'", format))?;
+    if format != SAVE_STRING_FORMAT {
+        return Err(format!(
+            "save string format {} is not supported (expected {})",
+            format, SAVE_STRING_FORMAT
+        ));
+    }
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|e| format!("save string is not valid base64: {}", e))?;
+    let split = payload
+        .len()
+        .checked_sub(4)
+        .ok_or("save string is truncated")?;
+    let (compressed, checksum) = payload.split_at(split);
+    let json = decompress_to_vec_with_limit(compressed, MAX_SAVE_BYTES)
+        .map_err(|e| format!("save string is corrupted: {}", e))?;
+    let checksum = u32::from_be_bytes(checksum.try_into().expect("split leaves 4 bytes"));
+    if crc32fast::hash(&json) != checksum {
+        return Err("save string checksum does not match".to_string());
+    }
+    serde_json::from_slice(&json).map_err(|e| format!("save string is corrupted: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{parse_save, GameStorage};
+    use crate::upgrades::load_upgrades_config;
+
+    fn fixture() -> Value {
+        serde_json::from_str(include_str!("fixtures/v1_envelope.json")).unwrap()
+    }
+
+    #[test]
+    fn round_trips_and_is_smaller_than_json() {
+        let save = fixture();
+        let text = encode_save_string(&save).unwrap();
+        assert!(text.starts_with("IDLE1:"));
+        assert!(text.len() < serde_json::to_string(&save).unwrap().len());
+        assert_eq!(decode_save_string(&text).unwrap(), save);
+    }
+
+    #[test]
+    fn round_trips_through_game_storage() {
+        let config = load_upgrades_config();
+        let state = parse_save(fixture(), &config).unwrap().state;
+        let text = GameStorage::to_save_string(&state).unwrap();
+        let loaded = GameStorage::from_save_string(&text, &config).unwrap();
+        assert!(loaded.issues.is_empty());
+        assert_eq!(loaded.state, state);
+    }
+
+    #[test]
+    fn ignores_whitespace() {
+        let text = encode_save_string(&fixture()).unwrap();
+        let wrapped = format!("  {}\n{}\n", &text[..20], &text[20..]);
+        assert_eq!(decode_save_string(&wrapped).unwrap(), fixture());
+    }
+
+    #[test]
+    fn rejects_tampered_and_foreign_strings() {
+        let text = encode_save_string(&fixture()).unwrap();
+        let mut tampered = text.clone().into_bytes();
+        let last = tampered.len() - 1;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        assert!(decode_save_string(&String::from_utf8(tampered).unwrap()).is_err());
+        assert!(decode_save_string("hello").is_err());
+        assert!(decode_save_string(&text.replacen("IDLE1", "IDLE9", 1))
+            .unwrap_err()
+            .contains("not supported"));
+    }
+}
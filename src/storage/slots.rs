@@ -0,0 +1,184 @@
+use crate::game::{GameState, Resources};
+use serde::{Deserialize, Serialize};
+
+/// The slot that existed before there were slots; it keeps the original
+/// save key, so older saves show up in it.
+pub const DEFAULT_SLOT: &str = "default";
+
+/// What the slot picker shows about a slot without loading its save.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SlotMetadata {
+    pub id: String,
+    pub name: String,
+    /// When the slot was last saved, in milliseconds; 0 if it never was.
+    #[serde(default)]
+    pub last_played: f64,
+    #[serde(default)]
+    pub resources: Resources,
+    #[serde(default)]
+    pub time_played: f64,
+}
+
+impl SlotMetadata {
+    fn new(id: String, name: String) -> Self {
+        Self {
+            id,
+            name,
+            last_played: 0.0,
+            resources: Resources::default(),
+            time_played: 0.0,
+        }
+    }
+
+    /// Whether the slot has ever been saved to.
+    pub fn is_empty(&self) -> bool {
+        self.last_played <= 0.0
+    }
+
+    pub fn record(&mut self, state: &GameState) {
+        self.last_played = state.last_saved;
+        self.resources = state.resources.clone();
+        self.time_played = state.statistics.time_played;
+    }
+
+    /// Back to how a new slot looks, for when its save was deleted.
+    pub fn forget_progress(&mut self) {
+        *self = Self::new(self.id.clone(), self.name.clone());
+    }
+}
+
+/// The index of save slots and which one the game is playing.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SaveSlots {
+    pub active: String,
+    pub slots: Vec<SlotMetadata>,
+}
+
+impl Default for SaveSlots {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_SLOT.to_string(),
+            slots: vec![SlotMetadata::new(
+                DEFAULT_SLOT.to_string(),
+                "Slot 1".to_string(),
+            )],
+        }
+    }
+}
+
+impl SaveSlots {
+    pub fn get(&self, id: &str) -> Option<&SlotMetadata> {
+        self.slots.iter().find(|slot| slot.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Result<&mut SlotMetadata, String> {
+        self.slots
+            .iter_mut()
+            .find(|slot| slot.id == id)
+            .ok_or_else(|| format!("Unknown save slot '{}'", id))
+    }
+
+    pub fn active_mut(&mut self) -> &mut SlotMetadata {
+        if self.get(&self.active).is_none() {
+            // The index was edited by hand; fall back to a slot that exists.
+            self.active = self.slots[0].id.clone();
+        }
+        let active = self.active.clone();
+        self.get_mut(&active).expect("active slot exists")
+    }
+
+    /// An id no slot uses yet: `slot2`, `slot3`, and so on.
+    fn unused_id(&self) -> String {
+        (2..)
+            .map(|n| format!("slot{}", n))
+            .find(|id| self.get(id).is_none())
+            .expect("ids are unbounded")
+    }
+
+    fn check_name(name: &str) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Slot name cannot be empty".to_string());
+        }
+        Ok(name.to_string())
+    }
+
+    /// Adds an empty slot and returns its id.
+    pub fn create(&mut self, name: &str) -> Result<String, String> {
+        let name = Self::check_name(name)?;
+        let id = self.unused_id();
+        self.slots.push(SlotMetadata::new(id.clone(), name));
+        Ok(id)
+    }
+
+    pub fn rename(&mut self, id: &str, name: &str) -> Result<(), String> {
+        let name = Self::check_name(name)?;
+        self.get_mut(id)?.name = name;
+        Ok(())
+    }
+
+    /// Adds a copy of a slot's metadata and returns the copy's id. Copying
+    /// the save itself is up to the caller.
+    pub fn duplicate(&mut self, id: &str) -> Result<String, String> {
+        let mut copy = self.get_mut(id)?.clone();
+        copy.id = self.unused_id();
+        copy.name = format!("{} (copy)", copy.name);
+        let copy_id = copy.id.clone();
+        self.slots.push(copy);
+        Ok(copy_id)
+    }
+
+    /// Removes a slot. Deleting the active slot switches to the first one
+    /// left; the last slot cannot be deleted.
+    pub fn delete(&mut self, id: &str) -> Result<(), String> {
+        if self.slots.len() <= 1 {
+            return Err("The last save slot cannot be deleted".to_string());
+        }
+        self.get_mut(id)?;
+        self.slots.retain(|slot| slot.id != id);
+        if self.active == id {
+            self.active = self.slots[0].id.clone();
+        }
+        Ok(())
+    }
+
+    pub fn switch(&mut self, id: &str) -> Result<(), String> {
+        self.get_mut(id)?;
+        self.active = id.to_string();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manages_slots() {
+        let mut slots = SaveSlots::default();
+        let second = slots.create("Speedrun").unwrap();
+        assert_eq!(second, "slot2");
+        assert!(slots.create("   ").is_err());
+
+        slots.rename(&second, "Challenge run").unwrap();
+        let copy = slots.duplicate(&second).unwrap();
+        assert_eq!(slots.get(&copy).unwrap().name, "Challenge run (copy)");
+
+        slots.switch(&copy).unwrap();
+        assert!(slots.switch("missing").is_err());
+        slots.delete(&copy).unwrap();
+        assert_eq!(slots.active, DEFAULT_SLOT);
+        assert_eq!(slots.slots.len(), 2);
+        // The deleted slot's id is free again.
+        assert_eq!(slots.create("Another").unwrap(), copy);
+    }
+
+    #[test]
+    fn keeps_at_least_one_slot() {
+        let mut slots = SaveSlots::default();
+        assert!(slots.delete(DEFAULT_SLOT).is_err());
+        let second = slots.create("Second").unwrap();
+        slots.delete(DEFAULT_SLOT).unwrap();
+        assert_eq!(slots.active, second);
+    }
+}
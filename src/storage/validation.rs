@@ -0,0 +1,236 @@
+use crate::game::{GameState, SaveIssue};
+use crate::storage::migrate_save;
+use crate::upgrades::UpgradesConfig;
+use serde_json::Value;
+use std::fmt;
+
+/// Why a save could not be loaded at all.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveError {
+    /// The save is not valid JSON or could not be read.
+    Unreadable(String),
+    /// The save is JSON but not a game state this version understands.
+    Incompatible(String),
+    /// The save was read, but some of its values could not be repaired.
+    Rejected(Vec<SaveIssue>),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Unreadable(error) => write!(f, "the save could not be read: {}", error),
+            SaveError::Incompatible(error) => write!(f, "the save is not compatible: {}", error),
+            SaveError::Rejected(issues) => {
+                let issues = issues
+                    .iter()
+                    .filter(|issue| issue.is_fatal())
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                write!(f, "the save has invalid values: {}", issues)
+            }
+        }
+    }
+}
+
+/// A save that passed validation, with whatever had to be repaired on the way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedSave {
+    pub state: GameState,
+    pub issues: Vec<SaveIssue>,
+}
+
+/// What to tell the player about a save they just loaded, labelled with
+/// where it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveProblems {
+    Repaired {
+        source: String,
+        issues: Vec<SaveIssue>,
+    },
+    Failed {
+        source: String,
+        error: SaveError,
+    },
+}
+
+impl SaveProblems {
+    /// The problems of loading from `source`, or `None` if there were none.
+    pub fn from_result(source: &str, result: &Result<LoadedSave, SaveError>) -> Option<Self> {
+        match result {
+            Ok(loaded) if loaded.issues.is_empty() => None,
+            Ok(loaded) => Some(SaveProblems::Repaired {
+                source: source.to_string(),
+                issues: loaded.issues.clone(),
+            }),
+            Err(error) => Some(SaveProblems::Failed {
+                source: source.to_string(),
+                error: error.clone(),
+            }),
+        }
+    }
+}
+
+/// Parses a save of any version: migrates it, then repairs what it can.
+pub fn parse_save(save: Value, upgrades_config: &UpgradesConfig) -> Result<LoadedSave, SaveError> {
+    let mut state = migrate_save(save).map_err(SaveError::Incompatible)?;
+    let issues = state.sanitize(upgrades_config);
+    if issues.iter().any(SaveIssue::is_fatal) {
+        return Err(SaveError::Rejected(issues));
+    }
+    Ok(LoadedSave { state, issues })
+}
+
+/// [`parse_save`] for save text, e.g. the contents of an exported file.
+pub fn parse_save_str(
+    contents: &str,
+    upgrades_config: &UpgradesConfig,
+) -> Result<LoadedSave, SaveError> {
+    let save = serde_json::from_str::<Value>(contents)
+        .map_err(|error| SaveError::Unreadable(error.to_string()))?;
+    parse_save(save, upgrades_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::BigNum;
+    use crate::upgrades::load_upgrades_config;
+
+    fn envelope(state: Value) -> Value {
+        let mut save: Value =
+            serde_json::from_str(include_str!("fixtures/v1_envelope.json")).unwrap();
+        let fields = state.as_object().unwrap().clone();
+        save["state"].as_object_mut().unwrap().extend(fields);
+        save
+    }
+
+    #[test]
+    fn accepts_valid_save_unchanged() {
+        let loaded = parse_save(envelope(serde_json::json!({})), &load_upgrades_config()).unwrap();
+        assert!(loaded.issues.is_empty());
+        assert_eq!(loaded.state.resources.get("gold"), BigNum::from(120));
+    }
+
+    #[test]
+    fn repairs_out_of_range_values() {
+        let save = envelope(serde_json::json!({
+            "resources": { "gold": "-5", "mithril": "3" },
+            "upgrades": { "auto_clicker": -2, "time_machine": 1 },
+            "generated": { "time_machine": "5" },
+            "cost_scaling": 0.0,
+            "last_saved": -1.0
+        }));
+        let loaded = parse_save(save, &load_upgrades_config()).unwrap();
+        let state = &loaded.state;
+        assert_eq!(state.resources.get("gold"), BigNum::ZERO);
+        assert_eq!(state.resources.get("mithril"), BigNum::ZERO);
+        assert_eq!(state.upgrades.level("auto_clicker"), 0);
+        assert_eq!(state.upgrades.level("time_machine"), 0);
+        assert_eq!(state.generated.get("time_machine"), BigNum::ZERO);
+        assert_eq!(
+            state.cost_scaling,
+            load_upgrades_config().defaults.cost_scaling
+        );
+        assert_eq!(state.last_saved, 0.0);
+        assert_eq!(loaded.issues.len(), 7);
+        assert!(!loaded.issues.iter().any(SaveIssue::is_fatal));
+    }
+
+    #[test]
+    fn drops_invalid_buffs() {
+        let save = envelope(serde_json::json!({
+            "buffs": [
+                { "id": "discount", "target": "cost", "multiplier": -1.0, "started_at": 0.0, "duration": 10.0 },
+                { "id": "frenzy", "target": "production", "multiplier": 2.0, "started_at": 0.0, "duration": -5.0 },
+                { "id": "lucky", "target": "click_value", "multiplier": 2.0, "started_at": 0.0, "duration": 10.0 }
+            ]
+        }));
+        let loaded = parse_save(save, &load_upgrades_config()).unwrap();
+        let buffs: Vec<_> = loaded
+            .state
+            .buffs
+            .iter()
+            .map(|buff| buff.id.as_str())
+            .collect();
+        assert_eq!(buffs, ["lucky"]);
+        assert_eq!(loaded.issues.len(), 2);
+    }
+
+    #[test]
+    fn drops_invalid_challenge_multipliers() {
+        let save = envelope(serde_json::json!({
+            "challenges": {
+                "completed": [],
+                "active": {
+                    "id": "hands_only",
+                    "started_at": 0.0,
+                    "modifiers": [
+                        { "kind": "cost_multiplier", "multiplier": -2.0 },
+                        { "kind": "production_multiplier", "multiplier": 0.0 },
+                        { "kind": "time_limit", "seconds": 60.0 }
+                    ]
+                }
+            }
+        }));
+        let loaded = parse_save(save, &load_upgrades_config()).unwrap();
+        assert_eq!(loaded.state.challenge_modifiers().len(), 1);
+        assert_eq!(loaded.state.challenge_cost_multiplier(), 1.0);
+        assert_eq!(loaded.issues.len(), 2);
+    }
+
+    #[test]
+    fn repairs_statistics_amounts() {
+        let save = envelope(serde_json::json!({
+            "statistics": {
+                "clicks": 0,
+                "time_played": 0.0,
+                "earned": { "gold": "-5" },
+                "best_clicks_per_second": "-3"
+            }
+        }));
+        let loaded = parse_save(save, &load_upgrades_config()).unwrap();
+        let statistics = &loaded.state.statistics;
+        assert_eq!(statistics.earned.get("gold"), BigNum::ZERO);
+        assert_eq!(statistics.best_clicks_per_second, BigNum::ZERO);
+        assert_eq!(loaded.issues.len(), 2);
+
+        let save = envelope(serde_json::json!({
+            "statistics": { "clicks": 0, "time_played": 0.0, "spent": { "gold": "nan" } }
+        }));
+        assert!(matches!(
+            parse_save(save, &load_upgrades_config()),
+            Err(SaveError::Rejected(_))
+        ));
+    }
+
+    #[test]
+    fn resets_invalid_autobuyer_charge() {
+        let save = envelope(serde_json::json!({ "automation": { "rules": {}, "charge": -3.0 } }));
+        let loaded = parse_save(save, &load_upgrades_config()).unwrap();
+        assert_eq!(loaded.state.automation.charge, 0.0);
+        assert_eq!(loaded.issues.len(), 1);
+    }
+
+    #[test]
+    fn rejects_non_finite_amounts() {
+        let save = envelope(serde_json::json!({ "resources": { "gold": "inf" } }));
+        match parse_save(save, &load_upgrades_config()) {
+            Err(SaveError::Rejected(issues)) => assert!(issues.iter().any(SaveIssue::is_fatal)),
+            other => panic!("expected a rejected save, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_unreadable_and_incompatible_saves() {
+        let config = load_upgrades_config();
+        assert!(matches!(
+            parse_save_str("{ not json", &config),
+            Err(SaveError::Unreadable(_))
+        ));
+        assert!(matches!(
+            parse_save_str(r#"{ "version": 99, "state": {} }"#, &config),
+            Err(SaveError::Incompatible(_))
+        ));
+    }
+}
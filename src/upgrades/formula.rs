@@ -0,0 +1,525 @@
+use crate::game::BigNum;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Arithmetic expression from the config, such as
+/// `base_cost * cost_scaling ^ level` or `1 + resource.gems / 100`.
+///
+/// Supports numbers, `+ - * / ^`, parentheses, the functions `min`, `max`,
+/// `floor`, `round`, `sqrt`, `log10` and `abs`, and variables, which may be
+/// dotted like `resource.gold`. It is parsed once when the config loads;
+/// what the variables mean is up to whoever evaluates it.
+#[derive(Debug, Clone)]
+pub struct Formula {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Min,
+    Max,
+    Floor,
+    Round,
+    Sqrt,
+    Log10,
+    Abs,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "floor" => Self::Floor,
+            "round" => Self::Round,
+            "sqrt" => Self::Sqrt,
+            "log10" => Self::Log10,
+            "abs" => Self::Abs,
+            _ => return None,
+        })
+    }
+
+    /// Smallest and largest number of arguments accepted.
+    fn arity(self) -> (usize, usize) {
+        match self {
+            Self::Min | Self::Max => (1, usize::MAX),
+            _ => (1, 1),
+        }
+    }
+}
+
+impl Formula {
+    pub fn constant(value: f64) -> Self {
+        Self {
+            source: value.to_string(),
+            expr: Expr::Number(value),
+        }
+    }
+
+    /// The value if the formula is a plain number.
+    pub fn as_constant(&self) -> Option<f64> {
+        match self.expr {
+            Expr::Number(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Every variable the formula reads, in order of appearance.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = Vec::new();
+        collect_variables(&self.expr, &mut variables);
+        variables
+    }
+
+    /// Evaluates the formula, reading variables through `variable`. Unknown
+    /// variables count as zero; config validation rules them out up front.
+    pub fn evaluate(&self, variable: &dyn Fn(&str) -> Option<BigNum>) -> BigNum {
+        evaluate(&self.expr, variable)
+    }
+}
+
+fn collect_variables<'a>(expr: &'a Expr, variables: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Number(_) => {}
+        Expr::Variable(name) => variables.push(name),
+        Expr::Negate(inner) => collect_variables(inner, variables),
+        Expr::Binary(_, left, right) => {
+            collect_variables(left, variables);
+            collect_variables(right, variables);
+        }
+        Expr::Call(_, arguments) => {
+            for argument in arguments {
+                collect_variables(argument, variables);
+            }
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, variable: &dyn Fn(&str) -> Option<BigNum>) -> BigNum {
+    match expr {
+        Expr::Number(value) => BigNum::from(*value),
+        Expr::Variable(name) => variable(name).unwrap_or_default(),
+        Expr::Negate(inner) => -evaluate(inner, variable),
+        Expr::Binary(operator, left, right) => {
+            let left = evaluate(left, variable);
+            let right = evaluate(right, variable);
+            match operator {
+                Operator::Add => left + right,
+                Operator::Subtract => left - right,
+                Operator::Multiply => left * right,
+                Operator::Divide => left / right,
+                Operator::Power => left.powf(right.to_f64()),
+            }
+        }
+        Expr::Call(function, arguments) => {
+            let mut values = arguments
+                .iter()
+                .map(|argument| evaluate(argument, variable));
+            let first = values.next().unwrap_or_default();
+            match function {
+                Function::Min => values.fold(first, BigNum::min),
+                Function::Max => values.fold(first, BigNum::max),
+                Function::Floor => first.floor(),
+                Function::Round => first.round(),
+                Function::Sqrt => first.powf(0.5),
+                Function::Log10 => BigNum::from(first.log10()),
+                Function::Abs => {
+                    if first.is_negative() {
+                        -first
+                    } else {
+                        first
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+    OpenParen,
+    CloseParen,
+    Comma,
+}
+
+/// Splits `source` into tokens, each with the character position it starts at.
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let start = index;
+        let c = chars[index];
+        if c.is_whitespace() {
+            index += 1;
+            continue;
+        }
+        let token = if c.is_ascii_digit() || c == '.' {
+            while index < chars.len()
+                && (chars[index].is_ascii_digit()
+                    || chars[index] == '.'
+                    || matches!(chars[index], 'e' | 'E')
+                    || (matches!(chars[index], '+' | '-') && matches!(chars[index - 1], 'e' | 'E')))
+            {
+                index += 1;
+            }
+            let text: String = chars[start..index].iter().collect();
+            let value = text
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{}' at position {}", text, start + 1))?;
+            Token::Number(value)
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while index < chars.len()
+                && (chars[index].is_ascii_alphanumeric() || matches!(chars[index], '_' | '.'))
+            {
+                index += 1;
+            }
+            Token::Identifier(chars[start..index].iter().collect())
+        } else {
+            index += 1;
+            match c {
+                '+' | '-' | '*' | '/' | '^' => Token::Operator(c),
+                '(' => Token::OpenParen,
+                ')' => Token::CloseParen,
+                ',' => Token::Comma,
+                _ => {
+                    return Err(format!(
+                        "unexpected character '{}' at position {}",
+                        c,
+                        start + 1
+                    ))
+                }
+            }
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent parser. Precedence from loosest to tightest:
+/// `+ -`, `* /`, unary `-`, then right-associative `^`.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    length: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map_or(self.length, |(position, _)| *position)
+            + 1
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(_, token)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), String> {
+        let position = self.position();
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(format!("expected {} at position {}", description, position)),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        while let Some(Token::Operator(c @ ('+' | '-'))) = self.peek() {
+            let operator = if *c == '+' {
+                Operator::Add
+            } else {
+                Operator::Subtract
+            };
+            self.next();
+            let right = self.term()?;
+            left = Expr::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Operator(c @ ('*' | '/'))) = self.peek() {
+            let operator = if *c == '*' {
+                Operator::Multiply
+            } else {
+                Operator::Divide
+            };
+            self.next();
+            let right = self.unary()?;
+            left = Expr::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if let Some(Token::Operator('-')) = self.peek() {
+            self.next();
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.next();
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(
+                Operator::Power,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Identifier(name)) => {
+                if self.peek() != Some(&Token::OpenParen) {
+                    return Ok(Expr::Variable(name));
+                }
+                let function = Function::from_name(&name).ok_or_else(|| {
+                    format!("unknown function '{}' at position {}", name, position)
+                })?;
+                self.next();
+                let mut arguments = vec![self.expression()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.next();
+                    arguments.push(self.expression()?);
+                }
+                self.expect(Token::CloseParen, "')'")?;
+                let (min, max) = function.arity();
+                if arguments.len() < min || arguments.len() > max {
+                    return Err(format!(
+                        "'{}' at position {} takes {} argument{}, got {}",
+                        name,
+                        position,
+                        min,
+                        if min == 1 { "" } else { "s" },
+                        arguments.len()
+                    ));
+                }
+                Ok(Expr::Call(function, arguments))
+            }
+            Some(Token::OpenParen) => {
+                let inner = self.expression()?;
+                self.expect(Token::CloseParen, "')'")?;
+                Ok(inner)
+            }
+            Some(_) => Err(format!("unexpected symbol at position {}", position)),
+            None => Err("unexpected end of formula".to_string()),
+        }
+    }
+}
+
+impl FromStr for Formula {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |e: String| format!("Invalid formula '{}': {}", s, e);
+        let tokens = tokenize(s).map_err(invalid)?;
+        let mut parser = Parser {
+            tokens,
+            index: 0,
+            length: s.chars().count(),
+        };
+        let expr = parser.expression().map_err(invalid)?;
+        if parser.index < parser.tokens.len() {
+            return Err(invalid(format!(
+                "unexpected symbol at position {}",
+                parser.position()
+            )));
+        }
+        Ok(Self {
+            source: s.trim().to_string(),
+            expr,
+        })
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Two formulas are equal when they are written the same way.
+impl PartialEq for Formula {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+/// Plain numbers stay JSON numbers; anything else is written as its source.
+impl Serialize for Formula {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.as_constant() {
+            Some(value) => serializer.serialize_f64(value),
+            None => serializer.serialize_str(&self.source),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Formula {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FormulaVisitor;
+
+        impl Visitor<'_> for FormulaVisitor {
+            type Value = Formula;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a number or a formula string")
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Formula, E> {
+                Ok(Formula::constant(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Formula, E> {
+                Ok(Formula::constant(value as f64))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Formula, E> {
+                Ok(Formula::constant(value as f64))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Formula, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(FormulaVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(source: &str) -> f64 {
+        let formula: Formula = source.parse().unwrap();
+        formula
+            .evaluate(&|name| match name {
+                "level" => Some(BigNum::from(3)),
+                "resource.gems" => Some(BigNum::from(50)),
+                _ => None,
+            })
+            .to_f64()
+    }
+
+    fn assert_evaluates(source: &str, expected: f64) {
+        let value = evaluate(source);
+        assert!(
+            (value - expected).abs() < 1e-9,
+            "'{}' gave {}, expected {}",
+            source,
+            value,
+            expected
+        );
+    }
+
+    fn parse_error(source: &str) -> String {
+        source.parse::<Formula>().unwrap_err()
+    }
+
+    #[test]
+    fn follows_precedence_and_associativity() {
+        assert_evaluates("1 + 2 * 3", 7.0);
+        assert_evaluates("(1 + 2) * 3", 9.0);
+        assert_evaluates("10 - 4 - 3", 3.0);
+        assert_evaluates("12 / 2 / 3", 2.0);
+        assert_evaluates("2 ^ 3 ^ 2", 512.0);
+        assert_evaluates("2 * 3 ^ 2", 18.0);
+        assert_evaluates("1.5e3 + 2E-1", 1500.2);
+    }
+
+    #[test]
+    fn binds_unary_minus_looser_than_powers() {
+        assert_evaluates("-2 ^ 2", -4.0);
+        assert_evaluates("2 ^ -1", 0.5);
+        assert_evaluates("--3", 3.0);
+        assert_evaluates("3 * -level", -9.0);
+    }
+
+    #[test]
+    fn calls_functions() {
+        assert_evaluates("min(5, level, 4)", 3.0);
+        assert_evaluates("max(1, 2)", 2.0);
+        assert_evaluates("floor(2.7) + round(2.5)", 5.0);
+        assert_evaluates("sqrt(16)", 4.0);
+        assert_evaluates("log10(1000)", 3.0);
+        assert_evaluates("abs(-2)", 2.0);
+    }
+
+    #[test]
+    fn reads_variables_and_zeroes_unknown_ones() {
+        assert_evaluates("1 + resource.gems / 100", 1.5);
+        assert_evaluates("level * missing + 1", 1.0);
+        let formula: Formula = "level * missing + resource.gems".parse().unwrap();
+        assert_eq!(formula.variables(), ["level", "missing", "resource.gems"]);
+    }
+
+    #[test]
+    fn rejects_malformed_formulas() {
+        assert!(parse_error("").contains("unexpected end of formula"));
+        assert!(parse_error("1 +").contains("unexpected end of formula"));
+        assert!(parse_error("(1 + 2").contains("expected ')' at position 7"));
+        assert!(parse_error("1 2").contains("unexpected symbol at position 3"));
+        assert!(parse_error("2 $ 3").contains("unexpected character '$' at position 3"));
+        assert!(parse_error("1..2").contains("invalid number '1..2'"));
+        assert!(parse_error("pow(2, 3)").contains("unknown function 'pow'"));
+        assert!(parse_error("floor(1, 2)").contains("takes 1 argument, got 2"));
+        assert!(parse_error("min()").contains("unexpected symbol at position 5"));
+    }
+
+    #[test]
+    fn round_trips_through_serde() {
+        let constant: Formula = serde_json::from_str("2.5").unwrap();
+        assert_eq!(constant.as_constant(), Some(2.5));
+        assert_eq!(serde_json::to_string(&constant).unwrap(), "2.5");
+
+        let formula: Formula = serde_json::from_str(r#""base_cost * 2 ^ level""#).unwrap();
+        assert_eq!(formula.as_constant(), None);
+        assert_eq!(
+            serde_json::to_string(&formula).unwrap(),
+            r#""base_cost * 2 ^ level""#
+        );
+        assert!(serde_json::from_str::<Formula>(r#""2 *""#).is_err());
+    }
+}
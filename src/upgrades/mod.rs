@@ -1,21 +1,404 @@
+mod formula;
+mod validation;
+
+pub use formula::Formula;
+
+use crate::game::BigNum;
 use serde::{Deserialize, Serialize};
 use serde_json::from_str;
 
+/// Cost of an upgrade without a `cost` formula of its own.
+pub const DEFAULT_COST_FORMULA: &str = "base_cost * cost_scaling ^ level";
+
+/// Variables the cost and effect formulas of an upgrade can read, besides
+/// `resource.<id>` for the amount held of each resource.
+pub const FORMULA_VARIABLES: [&str; 4] = ["level", "owned", "base_cost", "cost_scaling"];
+
+/// What owning a level of an upgrade does to the economy. Every `amount` is
+/// a [`Formula`] over [`FORMULA_VARIABLES`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UpgradeEffect {
+    /// Each level adds `amount` to the click multiplier.
+    ClickMultiplier { amount: Formula },
+    /// Each level adds `amount` base units per second of `resource`, which
+    /// defaults to the primary resource.
+    AutoClicker {
+        amount: Formula,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        resource: Option<String>,
+    },
+    /// Each level adds `amount` units per second of the `produces` upgrade,
+    /// the tier below it in a generator chain.
+    Generator { amount: Formula, produces: String },
+    /// Unlocks the autobuyer; each level adds `amount` purchases per second
+    /// it can make.
+    Autobuyer { amount: Formula },
+}
+
+impl UpgradeEffect {
+    pub fn amount(&self) -> &Formula {
+        match self {
+            UpgradeEffect::ClickMultiplier { amount }
+            | UpgradeEffect::AutoClicker { amount, .. }
+            | UpgradeEffect::Generator { amount, .. }
+            | UpgradeEffect::Autobuyer { amount } => amount,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResourceDefinition {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UpgradeDefinition {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+    pub base_cost: BigNum,
+    /// Resource the cost is paid in; defaults to the primary resource.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_resource: Option<String>,
+    /// Falls back to the game's global `cost_scaling` when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_scaling: Option<f64>,
+    /// Cost of the next level. Defaults to [`DEFAULT_COST_FORMULA`], which
+    /// also has a closed form for bulk purchases.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<Formula>,
+    pub effect: UpgradeEffect,
+    /// All of these must hold before the upgrade can be bought.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<Prerequisite>,
+    /// Output multipliers that kick in at set levels.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub milestones: Vec<Milestone>,
+    /// Leave the upgrade out of the shop entirely while it is locked,
+    /// instead of showing it greyed out.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden_while_locked: bool,
+}
+
+/// Once `level` levels of an upgrade are bought, its effect is multiplied
+/// by `multiplier`. Reached milestones compound.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Milestone {
+    pub level: i32,
+    pub multiplier: f64,
+}
+
+/// A condition gating an upgrade.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Prerequisite {
+    /// At least `level` levels of `upgrade`.
+    UpgradeLevel { upgrade: String, level: i32 },
+    /// Holding at least `amount` of `resource` at once.
+    Resource { resource: String, amount: BigNum },
+    /// The achievement with this id is unlocked.
+    Achievement { achievement: String },
+}
+
+/// What has to be true for an achievement to unlock or a challenge to be
+/// completed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AchievementCondition {
+    /// Holding at least `amount` of `resource` at once.
+    Resource { resource: String, amount: BigNum },
+    /// Owning at least `level` levels of `upgrade`.
+    UpgradeLevel { upgrade: String, level: i32 },
+    /// Earning `amount` of the primary resource across every run.
+    LifetimeEarned { amount: BigNum },
+    /// Clicking manually `count` times across every run.
+    Clicks { count: u64 },
+    /// Playing for `seconds` across every run, not counting time away.
+    TimePlayed { seconds: f64 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AchievementDefinition {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+    pub description: String,
+    pub condition: AchievementCondition,
+    /// Permanent click and production multiplier granted once unlocked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bonus: Option<f64>,
+}
+
+/// A rule changed for the length of a challenge run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChallengeModifier {
+    /// These upgrades cannot be bought.
+    DisabledUpgrades { upgrades: Vec<String> },
+    /// Every upgrade costs `multiplier` times as much.
+    CostMultiplier { multiplier: f64 },
+    /// Auto clickers produce `multiplier` times as much.
+    ProductionMultiplier { multiplier: f64 },
+    /// The goal has to be reached within `seconds` of play.
+    TimeLimit { seconds: f64 },
+}
+
+/// A fresh run under modified rules, played in place of the normal run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChallengeDefinition {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+    pub description: String,
+    pub modifiers: Vec<ChallengeModifier>,
+    /// Reaching this completes the challenge.
+    pub goal: AchievementCondition,
+    /// Permanent click and production multiplier granted once completed.
+    pub bonus: f64,
+}
+
+/// What an active buff multiplies.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BuffTarget {
+    ClickValue,
+    /// Output of the auto clickers, i.e. resources per second.
+    Production,
+    /// Upgrade costs; a multiplier below 1 is a discount.
+    Cost,
+}
+
+/// What happens when a buff is activated while already active.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BuffStacking {
+    /// Restart the timer at the full duration.
+    #[default]
+    Refresh,
+    /// Add the full duration to the time remaining.
+    Extend,
+    /// Run another copy alongside; the multipliers compound.
+    Stack,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BuffDefinition {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+    pub target: BuffTarget,
+    pub multiplier: f64,
+    /// Seconds of play the buff lasts.
+    pub duration: f64,
+    #[serde(default)]
+    pub stacking: BuffStacking,
+}
+
+/// What collecting a golden click grants.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-pub struct UpgradeParameters {
-    pub base_cost: i32,
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GoldenClickReward {
+    /// An instant payout of `seconds` worth of current production.
+    ProductionSeconds { seconds: f64 },
+    /// A flat amount of `resource`.
+    Resource { resource: String, amount: BigNum },
+    /// Starts the buff with this id.
+    Buff { buff: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WeightedReward {
+    pub weight: f64,
+    pub reward: GoldenClickReward,
+}
+
+/// A bonus that shows up for a few seconds and pays out when clicked.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct GoldenClickConfig {
+    pub spawn_chance_per_second: f64,
+    /// Seconds it stays clickable before disappearing.
+    pub lifetime: f64,
+    /// One is picked per click, with odds proportional to its weight.
+    pub rewards: Vec<WeightedReward>,
+}
+
+/// Chance for a manual click to be worth `multiplier` times as much.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CriticalClickConfig {
+    pub chance: f64,
+    pub multiplier: f64,
+}
+
+/// Spawn rates and rewards of random events, read from `random_events.json`.
+/// Everything defaults to off.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RandomEventsConfig {
+    pub golden_click: GoldenClickConfig,
+    pub critical_click: CriticalClickConfig,
+}
+
+/// Initial values for the tunable parameters exposed in the developer panel.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DefaultParameters {
+    pub base_multiplier: f64,
+    pub cost_scaling: f64,
+    pub auto_clicker_efficiency: f64,
+}
+
+/// Multipliers a difficulty applies over the config's parameters. Any left
+/// out default to 1, i.e. unchanged.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DifficultyMultipliers {
+    pub base_multiplier: f64,
     pub cost_scaling: f64,
-    pub efficiency: Option<f64>,
-    pub multiplier: Option<f64>,
+    pub auto_clicker_efficiency: f64,
+    /// Applies to every upgrade's `base_cost`.
+    pub base_cost: f64,
+    /// Applies to the offline `efficiency`.
+    pub offline_efficiency: f64,
+}
+
+impl Default for DifficultyMultipliers {
+    fn default() -> Self {
+        Self {
+            base_multiplier: 1.0,
+            cost_scaling: 1.0,
+            auto_clicker_efficiency: 1.0,
+            base_cost: 1.0,
+            offline_efficiency: 1.0,
+        }
+    }
+}
+
+/// A named difficulty offered when starting a new game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DifficultyPreset {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub multipliers: DifficultyMultipliers,
+}
+
+/// How much production is credited for time spent with the game closed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OfflineConfig {
+    /// Fraction of the regular clicks per second earned while away.
+    pub efficiency: f64,
+    /// Longest absence, in seconds, that still earns anything.
+    pub max_seconds: f64,
+}
+
+/// Prestige points are `floor((lifetime_earned / divisor) ^ exponent)`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PrestigeConfig {
+    pub divisor: BigNum,
+    pub exponent: f64,
+    /// Extra click and production multiplier granted per prestige point.
+    pub bonus_per_point: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct UpgradesConfig {
-    pub auto_clicker: UpgradeParameters,
-    pub click_multiplier: UpgradeParameters,
+    /// The first resource is the primary one: clicks produce it and prestige
+    /// counts its lifetime earnings.
+    pub resources: Vec<ResourceDefinition>,
+    pub defaults: DefaultParameters,
+    pub prestige: PrestigeConfig,
+    pub offline: OfflineConfig,
+    pub upgrades: Vec<UpgradeDefinition>,
+    #[serde(default)]
+    pub achievements: Vec<AchievementDefinition>,
+    #[serde(default)]
+    pub buffs: Vec<BuffDefinition>,
+    #[serde(default)]
+    pub challenges: Vec<ChallengeDefinition>,
+    #[serde(default)]
+    pub difficulties: Vec<DifficultyPreset>,
+    #[serde(default)]
+    pub random_events: RandomEventsConfig,
+}
+
+impl UpgradesConfig {
+    pub fn get(&self, id: &str) -> Option<&UpgradeDefinition> {
+        self.upgrades.iter().find(|upgrade| upgrade.id == id)
+    }
+
+    pub fn achievement(&self, id: &str) -> Option<&AchievementDefinition> {
+        self.achievements
+            .iter()
+            .find(|achievement| achievement.id == id)
+    }
+
+    pub fn buff(&self, id: &str) -> Option<&BuffDefinition> {
+        self.buffs.iter().find(|buff| buff.id == id)
+    }
+
+    pub fn challenge(&self, id: &str) -> Option<&ChallengeDefinition> {
+        self.challenges.iter().find(|challenge| challenge.id == id)
+    }
+
+    pub fn difficulty(&self, id: &str) -> Option<&DifficultyPreset> {
+        self.difficulties
+            .iter()
+            .find(|difficulty| difficulty.id == id)
+    }
+
+    pub fn resource(&self, id: &str) -> Option<&ResourceDefinition> {
+        self.resources.iter().find(|resource| resource.id == id)
+    }
+
+    pub fn primary_resource(&self) -> &str {
+        &self.resources[0].id
+    }
+
+    /// Resource an upgrade's cost is paid in.
+    pub fn cost_resource<'a>(&'a self, upgrade: &'a UpgradeDefinition) -> &'a str {
+        upgrade
+            .cost_resource
+            .as_deref()
+            .unwrap_or_else(|| self.primary_resource())
+    }
+
+    /// Resource an auto clicker effect produces.
+    pub fn produced_resource<'a>(&'a self, effect: &'a UpgradeEffect) -> Option<&'a str> {
+        match effect {
+            UpgradeEffect::AutoClicker { resource, .. } => Some(
+                resource
+                    .as_deref()
+                    .unwrap_or_else(|| self.primary_resource()),
+            ),
+            UpgradeEffect::ClickMultiplier { .. }
+            | UpgradeEffect::Generator { .. }
+            | UpgradeEffect::Autobuyer { .. } => None,
+        }
+    }
+
+    /// Formats an amount with the icon of the resource it is counted in.
+    pub fn format_amount(&self, resource: &str, amount: BigNum) -> String {
+        match self.resource(resource) {
+            Some(definition) => format!("{} {}", amount, definition.icon),
+            None => format!("{} {}", amount, resource),
+        }
+    }
 }
 
 pub fn load_upgrades_config() -> UpgradesConfig {
     let upgrades_json = include_str!("../upgrades/upgrades.json");
-    from_str::<UpgradesConfig>(upgrades_json).expect("Failed to parse upgrades.json")
-}
\ No newline at end of file
+    let mut upgrades_config =
+        from_str::<UpgradesConfig>(upgrades_json).expect("Failed to parse upgrades.json");
+    let random_events_json = include_str!("../upgrades/random_events.json");
+    upgrades_config.random_events = from_str::<RandomEventsConfig>(random_events_json)
+        .expect("Failed to parse random_events.json");
+    assert!(
+        !upgrades_config.resources.is_empty(),
+        "upgrades.json must define at least one resource"
+    );
+    if let Err(error) = upgrades_config.validate() {
+        panic!("Invalid upgrades.json: {}", error);
+    }
+    upgrades_config
+}
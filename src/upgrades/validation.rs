@@ -0,0 +1,571 @@
+use crate::game::{is_valid_parameter, MAX_UPGRADE_LEVEL};
+use crate::upgrades::{
+    AchievementCondition, ChallengeModifier, GoldenClickReward, Prerequisite, UpgradeEffect,
+    UpgradesConfig, FORMULA_VARIABLES,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Something that can gate an upgrade, as a node of the unlock graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Node<'a> {
+    Upgrade(&'a str),
+    Achievement(&'a str),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+impl UpgradesConfig {
+    /// Runs every consistency check on the config.
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_ids()?;
+        self.validate_parameters()?;
+        self.validate_resources()?;
+        self.validate_generators()?;
+        self.validate_prerequisites()?;
+        self.validate_formulas()?;
+        self.validate_challenges()
+    }
+
+    /// Checks that no two resources, upgrades, achievements, buffs,
+    /// challenges or difficulties share an id; lookups would only ever find
+    /// the first.
+    pub fn validate_ids(&self) -> Result<(), String> {
+        let kinds: [(&str, Vec<&str>); 6] = [
+            (
+                "resource",
+                self.resources.iter().map(|r| r.id.as_str()).collect(),
+            ),
+            (
+                "upgrade",
+                self.upgrades.iter().map(|u| u.id.as_str()).collect(),
+            ),
+            (
+                "achievement",
+                self.achievements.iter().map(|a| a.id.as_str()).collect(),
+            ),
+            ("buff", self.buffs.iter().map(|b| b.id.as_str()).collect()),
+            (
+                "challenge",
+                self.challenges.iter().map(|c| c.id.as_str()).collect(),
+            ),
+            (
+                "difficulty",
+                self.difficulties.iter().map(|d| d.id.as_str()).collect(),
+            ),
+        ];
+        for (kind, ids) in kinds {
+            let mut seen = BTreeSet::new();
+            if let Some(id) = ids.into_iter().find(|id| !seen.insert(*id)) {
+                return Err(format!("{} id '{}' is used more than once", kind, id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that buffs, difficulties and milestones only use positive
+    /// multipliers and durations, and that milestones are reachable levels.
+    pub fn validate_parameters(&self) -> Result<(), String> {
+        for buff in &self.buffs {
+            if !is_valid_parameter(buff.multiplier) {
+                return Err(format!(
+                    "buff '{}' has multiplier {}, expected a number above 0",
+                    buff.id, buff.multiplier
+                ));
+            }
+            if !is_valid_parameter(buff.duration) {
+                return Err(format!(
+                    "buff '{}' lasts {} seconds, expected a number above 0",
+                    buff.id, buff.duration
+                ));
+            }
+        }
+        for difficulty in &self.difficulties {
+            let multipliers = &difficulty.multipliers;
+            let fields = [
+                ("base_multiplier", multipliers.base_multiplier),
+                ("cost_scaling", multipliers.cost_scaling),
+                (
+                    "auto_clicker_efficiency",
+                    multipliers.auto_clicker_efficiency,
+                ),
+                ("base_cost", multipliers.base_cost),
+                ("offline_efficiency", multipliers.offline_efficiency),
+            ];
+            if let Some((field, value)) =
+                fields.iter().find(|(_, value)| !is_valid_parameter(*value))
+            {
+                return Err(format!(
+                    "difficulty '{}' has {} {}, expected a number above 0",
+                    difficulty.id, field, value
+                ));
+            }
+        }
+        for upgrade in &self.upgrades {
+            for milestone in &upgrade.milestones {
+                if !(1..=MAX_UPGRADE_LEVEL).contains(&milestone.level) {
+                    return Err(format!(
+                        "'{}' has a milestone at level {}, expected 1..={}",
+                        upgrade.id, milestone.level, MAX_UPGRADE_LEVEL
+                    ));
+                }
+                if !is_valid_parameter(milestone.multiplier) {
+                    return Err(format!(
+                        "'{}' milestone at level {} has multiplier {}, expected a number above 0",
+                        upgrade.id, milestone.level, milestone.multiplier
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that upgrades are only paid in and produce resources that
+    /// exist, and that golden clicks only grant resources and buffs that do.
+    pub fn validate_resources(&self) -> Result<(), String> {
+        for upgrade in &self.upgrades {
+            if let Some(resource) = &upgrade.cost_resource {
+                if self.resource(resource).is_none() {
+                    return Err(format!(
+                        "'{}' costs unknown resource '{}'",
+                        upgrade.id, resource
+                    ));
+                }
+            }
+            if let UpgradeEffect::AutoClicker {
+                resource: Some(resource),
+                ..
+            } = &upgrade.effect
+            {
+                if self.resource(resource).is_none() {
+                    return Err(format!(
+                        "'{}' produces unknown resource '{}'",
+                        upgrade.id, resource
+                    ));
+                }
+            }
+        }
+        for reward in &self.random_events.golden_click.rewards {
+            match &reward.reward {
+                GoldenClickReward::Resource { resource, .. }
+                    if self.resource(resource).is_none() =>
+                {
+                    return Err(format!(
+                        "golden click grants unknown resource '{}'",
+                        resource
+                    ));
+                }
+                GoldenClickReward::Buff { buff } if self.buff(buff).is_none() => {
+                    return Err(format!("golden click grants unknown buff '{}'", buff));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that challenges only refer to upgrades and resources that
+    /// exist.
+    pub fn validate_challenges(&self) -> Result<(), String> {
+        for challenge in &self.challenges {
+            for modifier in &challenge.modifiers {
+                if let ChallengeModifier::DisabledUpgrades { upgrades } = modifier {
+                    if let Some(id) = upgrades.iter().find(|id| self.get(id).is_none()) {
+                        return Err(format!(
+                            "challenge '{}' disables unknown upgrade '{}'",
+                            challenge.id, id
+                        ));
+                    }
+                }
+            }
+            match &challenge.goal {
+                AchievementCondition::UpgradeLevel { upgrade, .. }
+                    if self.get(upgrade).is_none() =>
+                {
+                    return Err(format!(
+                        "challenge '{}' refers to unknown upgrade '{}'",
+                        challenge.id, upgrade
+                    ));
+                }
+                AchievementCondition::Resource { resource, .. }
+                    if self.resource(resource).is_none() =>
+                {
+                    return Err(format!(
+                        "challenge '{}' refers to unknown resource '{}'",
+                        challenge.id, resource
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that cost and effect formulas only read variables that exist.
+    /// Syntax errors are caught earlier, when the config is parsed.
+    pub fn validate_formulas(&self) -> Result<(), String> {
+        for upgrade in &self.upgrades {
+            let formulas = upgrade
+                .cost
+                .iter()
+                .map(|cost| ("cost", cost))
+                .chain(std::iter::once(("effect amount", upgrade.effect.amount())));
+            for (label, formula) in formulas {
+                for variable in formula.variables() {
+                    let known = FORMULA_VARIABLES.contains(&variable)
+                        || variable
+                            .strip_prefix("resource.")
+                            .is_some_and(|resource| self.resource(resource).is_some());
+                    if !known {
+                        return Err(format!(
+                            "'{}' {} '{}' uses unknown variable '{}' (expected one of {} or resource.<id>)",
+                            upgrade.id,
+                            label,
+                            formula,
+                            variable,
+                            FORMULA_VARIABLES.join(", ")
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every generator feeds an upgrade that produces something
+    /// and that no chain of generators loops back on itself.
+    pub fn validate_generators(&self) -> Result<(), String> {
+        for upgrade in &self.upgrades {
+            let mut current = upgrade;
+            let mut steps = 0;
+            while let UpgradeEffect::Generator { produces, .. } = &current.effect {
+                let target = self.get(produces).ok_or_else(|| {
+                    format!("'{}' produces unknown upgrade '{}'", current.id, produces)
+                })?;
+                if let UpgradeEffect::ClickMultiplier { .. } | UpgradeEffect::Autobuyer { .. } =
+                    target.effect
+                {
+                    return Err(format!(
+                        "'{}' produces '{}', which is not a producer",
+                        current.id, produces
+                    ));
+                }
+                steps += 1;
+                if steps > self.upgrades.len() {
+                    return Err(format!("generator chain from '{}' loops", upgrade.id));
+                }
+                current = target;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that prerequisites and achievement conditions only refer to
+    /// things that exist, and that no upgrade ends up requiring itself.
+    pub fn validate_prerequisites(&self) -> Result<(), String> {
+        for upgrade in &self.upgrades {
+            for prerequisite in &upgrade.requires {
+                match prerequisite {
+                    Prerequisite::UpgradeLevel { upgrade: id, .. } if self.get(id).is_none() => {
+                        return Err(format!(
+                            "'{}' requires unknown upgrade '{}'",
+                            upgrade.id, id
+                        ));
+                    }
+                    Prerequisite::Resource { resource, .. }
+                        if self.resource(resource).is_none() =>
+                    {
+                        return Err(format!(
+                            "'{}' requires unknown resource '{}'",
+                            upgrade.id, resource
+                        ));
+                    }
+                    Prerequisite::Achievement { achievement }
+                        if self.achievement(achievement).is_none() =>
+                    {
+                        return Err(format!(
+                            "'{}' requires unknown achievement '{}'",
+                            upgrade.id, achievement
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for achievement in &self.achievements {
+            match &achievement.condition {
+                AchievementCondition::UpgradeLevel { upgrade, .. }
+                    if self.get(upgrade).is_none() =>
+                {
+                    return Err(format!(
+                        "achievement '{}' refers to unknown upgrade '{}'",
+                        achievement.id, upgrade
+                    ));
+                }
+                AchievementCondition::Resource { resource, .. }
+                    if self.resource(resource).is_none() =>
+                {
+                    return Err(format!(
+                        "achievement '{}' refers to unknown resource '{}'",
+                        achievement.id, resource
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        let mut visits = BTreeMap::new();
+        let mut path = Vec::new();
+        for upgrade in &self.upgrades {
+            self.find_cycle(Node::Upgrade(&upgrade.id), &mut visits, &mut path)?;
+        }
+        Ok(())
+    }
+
+    /// What has to happen before `node` can: an upgrade waits on its
+    /// prerequisites, an achievement on the upgrade its condition names.
+    fn dependencies<'a>(&'a self, node: Node<'a>) -> Vec<Node<'a>> {
+        match node {
+            Node::Upgrade(id) => self
+                .get(id)
+                .map(|upgrade| {
+                    upgrade
+                        .requires
+                        .iter()
+                        .filter_map(|prerequisite| match prerequisite {
+                            Prerequisite::UpgradeLevel { upgrade, .. } => {
+                                Some(Node::Upgrade(upgrade))
+                            }
+                            Prerequisite::Achievement { achievement } => {
+                                Some(Node::Achievement(achievement))
+                            }
+                            Prerequisite::Resource { .. } => None,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Node::Achievement(id) => match self
+                .achievement(id)
+                .map(|achievement| &achievement.condition)
+            {
+                Some(AchievementCondition::UpgradeLevel { upgrade, .. }) => {
+                    vec![Node::Upgrade(upgrade)]
+                }
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    /// Depth-first search that fails with the offending path on a cycle.
+    fn find_cycle<'a>(
+        &'a self,
+        node: Node<'a>,
+        visits: &mut BTreeMap<Node<'a>, Visit>,
+        path: &mut Vec<Node<'a>>,
+    ) -> Result<(), String> {
+        match visits.get(&node) {
+            Some(Visit::Done) => return Ok(()),
+            Some(Visit::InProgress) => {
+                let start = path
+                    .iter()
+                    .position(|visited| *visited == node)
+                    .unwrap_or(0);
+                let cycle = path[start..]
+                    .iter()
+                    .chain(std::iter::once(&node))
+                    .map(|node| match node {
+                        Node::Upgrade(id) => format!("'{}'", id),
+                        Node::Achievement(id) => format!("achievement '{}'", id),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                return Err(format!("unlock requirements loop: {}", cycle));
+            }
+            None => {}
+        }
+        visits.insert(node, Visit::InProgress);
+        path.push(node);
+        for dependency in self.dependencies(node) {
+            self.find_cycle(dependency, visits, path)?;
+        }
+        path.pop();
+        visits.insert(node, Visit::Done);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::upgrades::{
+        load_upgrades_config, GoldenClickReward, Prerequisite, UpgradeDefinition, UpgradeEffect,
+        UpgradesConfig,
+    };
+
+    fn upgrade<'a>(config: &'a mut UpgradesConfig, id: &str) -> &'a mut UpgradeDefinition {
+        config
+            .upgrades
+            .iter_mut()
+            .find(|upgrade| upgrade.id == id)
+            .unwrap()
+    }
+
+    fn set_produces(config: &mut UpgradesConfig, id: &str, target: &str) {
+        match &mut upgrade(config, id).effect {
+            UpgradeEffect::Generator { produces, .. } => *produces = target.to_string(),
+            other => panic!("'{}' is not a generator: {:?}", id, other),
+        }
+    }
+
+    fn assert_rejected(config: &UpgradesConfig, expected: &str) {
+        let error = config.validate().unwrap_err();
+        assert!(error.contains(expected), "unexpected error: {}", error);
+    }
+
+    #[test]
+    fn accepts_the_shipped_config() {
+        assert_eq!(load_upgrades_config().validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_duplicate_ids() {
+        let mut config = load_upgrades_config();
+        let copy = config.upgrades[1].clone();
+        config.upgrades.push(copy);
+        assert_rejected(&config, "upgrade id 'auto_clicker' is used more than once");
+
+        let mut config = load_upgrades_config();
+        let copy = config.resources[0].clone();
+        config.resources.push(copy);
+        assert_rejected(&config, "resource id 'gold' is used more than once");
+
+        let mut config = load_upgrades_config();
+        let copy = config.buffs[0].clone();
+        config.buffs.push(copy);
+        assert_rejected(&config, "buff id");
+
+        let mut config = load_upgrades_config();
+        let copy = config.challenges[0].clone();
+        config.challenges.push(copy);
+        assert_rejected(&config, "challenge id 'hands_only' is used more than once");
+
+        let mut config = load_upgrades_config();
+        let copy = config.difficulties[0].clone();
+        config.difficulties.push(copy);
+        assert_rejected(&config, "difficulty id");
+    }
+
+    #[test]
+    fn rejects_invalid_buffs() {
+        let mut config = load_upgrades_config();
+        config.buffs[0].multiplier = -2.0;
+        assert_rejected(&config, "has multiplier -2, expected a number above 0");
+
+        let mut config = load_upgrades_config();
+        config.buffs[0].duration = 0.0;
+        assert_rejected(&config, "lasts 0 seconds");
+    }
+
+    #[test]
+    fn rejects_invalid_difficulty_multipliers() {
+        let mut config = load_upgrades_config();
+        config.difficulties[0].multipliers.cost_scaling = f64::NAN;
+        assert_rejected(&config, "has cost_scaling NaN");
+    }
+
+    #[test]
+    fn rejects_invalid_milestones() {
+        let mut config = load_upgrades_config();
+        upgrade(&mut config, "auto_clicker").milestones[0].level = 0;
+        assert_rejected(&config, "'auto_clicker' has a milestone at level 0");
+
+        let mut config = load_upgrades_config();
+        upgrade(&mut config, "auto_clicker").milestones[0].multiplier = 0.0;
+        assert_rejected(&config, "has multiplier 0, expected a number above 0");
+    }
+
+    #[test]
+    fn rejects_unknown_resources() {
+        let mut config = load_upgrades_config();
+        upgrade(&mut config, "auto_clicker").cost_resource = Some("gmes".to_string());
+        assert_rejected(&config, "'auto_clicker' costs unknown resource 'gmes'");
+
+        let mut config = load_upgrades_config();
+        upgrade(&mut config, "auto_clicker").effect = UpgradeEffect::AutoClicker {
+            amount: "1".parse().unwrap(),
+            resource: Some("gmes".to_string()),
+        };
+        assert_rejected(&config, "'auto_clicker' produces unknown resource 'gmes'");
+
+        let mut config = load_upgrades_config();
+        config.random_events.golden_click.rewards[0].reward = GoldenClickReward::Resource {
+            resource: "gmes".to_string(),
+            amount: 3.into(),
+        };
+        assert_rejected(&config, "golden click grants unknown resource 'gmes'");
+
+        let mut config = load_upgrades_config();
+        config.random_events.golden_click.rewards[0].reward = GoldenClickReward::Buff {
+            buff: "frenzzy".to_string(),
+        };
+        assert_rejected(&config, "golden click grants unknown buff 'frenzzy'");
+    }
+
+    #[test]
+    fn rejects_dangling_prerequisites() {
+        let mut config = load_upgrades_config();
+        upgrade(&mut config, "auto_clicker")
+            .requires
+            .push(Prerequisite::UpgradeLevel {
+                upgrade: "missing".to_string(),
+                level: 1,
+            });
+        assert_rejected(&config, "'auto_clicker' requires unknown upgrade 'missing'");
+
+        let mut config = load_upgrades_config();
+        upgrade(&mut config, "auto_clicker")
+            .requires
+            .push(Prerequisite::Achievement {
+                achievement: "missing".to_string(),
+            });
+        assert_rejected(
+            &config,
+            "'auto_clicker' requires unknown achievement 'missing'",
+        );
+    }
+
+    #[test]
+    fn rejects_dangling_and_looping_generators() {
+        let mut config = load_upgrades_config();
+        set_produces(&mut config, "clicker_factory", "missing");
+        assert_rejected(
+            &config,
+            "'clicker_factory' produces unknown upgrade 'missing'",
+        );
+
+        let mut config = load_upgrades_config();
+        set_produces(&mut config, "clicker_factory", "click_multiplier");
+        assert_rejected(&config, "which is not a producer");
+
+        let mut config = load_upgrades_config();
+        set_produces(&mut config, "clicker_factory", "factory_forge");
+        assert_rejected(&config, "loops");
+    }
+
+    #[test]
+    fn detects_unlock_cycles() {
+        let mut config = load_upgrades_config();
+        upgrade(&mut config, "auto_clicker")
+            .requires
+            .push(Prerequisite::UpgradeLevel {
+                upgrade: "factory_forge".to_string(),
+                level: 1,
+            });
+        assert_rejected(
+            &config,
+            "unlock requirements loop: 'auto_clicker' -> 'factory_forge' -> 'clicker_factory' -> 'auto_clicker'",
+        );
+    }
+}
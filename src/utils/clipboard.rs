@@ -0,0 +1,33 @@
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use yew::prelude::*;
+
+fn describe(error: JsValue) -> String {
+    error
+        .as_string()
+        .or_else(|| js_sys::Error::from(error).message().as_string())
+        .unwrap_or_else(|| "clipboard access was denied".to_string())
+}
+
+/// Copies `text` to the clipboard, then reports whether that worked.
+pub fn copy_to_clipboard(text: String, on_done: Callback<Result<(), String>>) {
+    let clipboard = web_sys::window().unwrap().navigator().clipboard();
+    spawn_local(async move {
+        let result = JsFuture::from(clipboard.write_text(&text)).await;
+        on_done.emit(result.map(|_| ()).map_err(describe));
+    });
+}
+
+/// Reads the text on the clipboard. Browsers may ask the player first, or
+/// refuse outright.
+pub fn read_from_clipboard(on_read: Callback<Result<String, String>>) {
+    let clipboard = web_sys::window().unwrap().navigator().clipboard();
+    spawn_local(async move {
+        let result = JsFuture::from(clipboard.read_text()).await;
+        on_read.emit(
+            result
+                .map_err(describe)
+                .map(|text| text.as_string().unwrap_or_default()),
+        );
+    });
+}
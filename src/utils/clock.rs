@@ -0,0 +1,11 @@
+use crate::game::Clock;
+
+/// Reads the time from the browser's `Date.now()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BrowserClock;
+
+impl Clock for BrowserClock {
+    fn now(&self) -> f64 {
+        js_sys::Date::now()
+    }
+}
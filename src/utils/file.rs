@@ -6,7 +6,7 @@ use log::error;
 use serde_json;
 
 use crate::game::GameState;
-use crate::upgrades::{UpgradeParameters, UpgradesConfig};
+use crate::upgrades::{load_upgrades_config, DefaultParameters};
 
 pub fn save_to_file<T: ?Sized + serde::Serialize>(data: &T, filename: &str) {
     if let Ok(json) = serde_json::to_string(data) {
@@ -28,7 +28,8 @@ pub fn save_to_file<T: ?Sized + serde::Serialize>(data: &T, filename: &str) {
     }
 }
 
-pub fn load_from_file(state: UseStateHandle<GameState>) {
+/// Opens a file picker and passes the text of the chosen file to `on_load`.
+pub fn load_from_file(on_load: Callback<String>) {
     let window = web_sys::window().unwrap();
     let document = window.document().unwrap();
     let input = document.create_element("input").unwrap();
@@ -36,22 +37,20 @@ pub fn load_from_file(state: UseStateHandle<GameState>) {
     input.set_attribute("style", "display: none;").unwrap();
     document.body().unwrap().append_child(&input).unwrap();
     let input: HtmlInputElement = input.dyn_into().unwrap();
-    let state_clone = state.clone();
+    let on_load_clone = on_load.clone();
     let closure = Closure::wrap(Box::new(move |event: Event| {
         let input: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
         let files = input.files().unwrap();
         if files.length() > 0 {
             let file = files.get(0).unwrap();
             let reader = FileReader::new().unwrap();
-            let state_clone = state_clone.clone();
+            let on_load_clone = on_load_clone.clone();
             let onloadend = Closure::wrap(Box::new(move |event: ProgressEvent| {
                 let reader: FileReader = event.target().unwrap().dyn_into().unwrap();
                 if let Ok(result) = reader.result() {
-                    if let Some(json) = result.as_string().as_deref() {
-                        match serde_json::from_str::<GameState>(&json) {
-                            Ok(loaded_state) => state_clone.set(loaded_state),
-                            Err(e) => error!("Failed to parse game state from file: {}", e),
-                        }
+                    match result.as_string() {
+                        Some(contents) => on_load_clone.emit(contents),
+                        None => error!("Failed to read file as text"),
                     }
                 }
             }) as Box<dyn FnMut(_)>);
@@ -65,20 +64,12 @@ pub fn load_from_file(state: UseStateHandle<GameState>) {
     closure.forget();
 }
 
-pub fn save_upgrades_config(game_state: UseStateHandle<GameState>) {
-    let upgrades_config = UpgradesConfig {
-        auto_clicker: UpgradeParameters {
-            base_cost: game_state.upgrades.auto_clicker,
-            cost_scaling: game_state.cost_scaling,
-            efficiency: Some(game_state.auto_clicker_efficiency),
-            multiplier: None,
-        },
-        click_multiplier: UpgradeParameters {
-            base_cost: game_state.upgrades.click_multiplier,
-            cost_scaling: game_state.cost_scaling,
-            efficiency: None,
-            multiplier: Some(game_state.base_multiplier),
-        },
+pub fn save_upgrades_config(game_state: &GameState) {
+    let mut upgrades_config = load_upgrades_config();
+    upgrades_config.defaults = DefaultParameters {
+        base_multiplier: game_state.base_multiplier,
+        cost_scaling: game_state.cost_scaling,
+        auto_clicker_efficiency: game_state.auto_clicker_efficiency,
     };
 
     save_to_file(&upgrades_config, "upgrades.json");
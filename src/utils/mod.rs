@@ -1,2 +1,4 @@
+pub mod clipboard;
+pub mod clock;
 pub mod file;
 